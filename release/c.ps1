@@ -1 +1 @@
-Invoke-Expression "gtexe -code $args"
+gtexe -code @args
@@ -1,2 +1,2 @@
-$x = Invoke-Expression "gtexe -get $args"
+$x = gtexe -get @args
 Start-Process $x
\ No newline at end of file
@@ -6,8 +6,19 @@ if (!$PSScriptRoot) {
   $CurrentDIR = $PSScriptRoot 
 }
 
-$Result = Invoke-Expression "$CurrentDIR\goto.exe $args"  
-$Result = $Result -split '#' 
+# Version and checksum of the protocol this script reads (see `goto.exe -verify-shell`), to detect a binary upgraded without this script
+$Protocol = & "$CurrentDIR\goto.exe" -verify-shell
+if ($Protocol -ne "2 2e4aeb44") {
+  Write-Error "gt: this script reads the protocol 2 2e4aeb44 of goto.exe, which now uses ${Protocol}: update the script"
+  return
+}
+
+# The arguments are passed as they are, so that a path with spaces stays one argument (e.g. `gt -add proj "My Projects"`)
+$Result = & "$CurrentDIR\goto.exe" @args
+# The fields are separated by `GOTO_OUTPUT_SEP`, the unit separator by default, as it can't be in a path
+# The path is the last field, kept whole even if it contains the separator (e.g. `c#proj` with `GOTO_OUTPUT_SEP=#`)
+$Sep = if ($env:GOTO_OUTPUT_SEP) { $env:GOTO_OUTPUT_SEP } else { [char]0x1f }
+$Result = $Result -split [regex]::Escape($Sep), 3 
 
 if ($Result.length -gt 3) {
   return
@@ -21,4 +32,4 @@ if ($Result[0] -eq "1") {
   # return
 }
 
-# Set-Location $Result[2]
+# Set-Location -LiteralPath $Result[2]
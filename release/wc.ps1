@@ -1,9 +1,9 @@
 # $in = $args[0]
 # $inn = $args[1]
 
-$path=$(Invoke-Expression "gtexe  $args -get")
+$path=$(gtexe @args -get)
 $path = $path -replace '\\', '/'
 $path = '/mnt/' + $path -replace ':', ''
 # Write-Host "Remote: $path"
 # Sleep 1
-Invoke-Expression "Code.exe --remote wsl+Ubuntu $path"
\ No newline at end of file
+Code.exe --remote wsl+Ubuntu $path
\ No newline at end of file
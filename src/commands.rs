@@ -1,74 +1,291 @@
-use crate::errors::SingleError;
-use crate::{general_error, user_error};
+use crate::errors::SingleError as _;
+use crate::{file_error, general_error, user_error};
 use core::fmt;
 use core::mem;
-use std::env;
+use core::sync::atomic;
+use core::{iter, str};
+use std::io::{self, Write as _};
+use std::path::Path;
+use std::{env, fs, process, thread, time};
+
+/// Factor of `-age` when none is given: the priorities lose a tenth of their value.
+pub const AGE_FACTOR: f64 = 0.9;
+
+/// Time after which a lock is considered stale (its process was probably killed), in milliseconds.
+const LOCK_TIMEOUT: u64 = 2000;
+
+/// Number of stale locks removed before giving up on a file.
+const LOCK_RETRIES: u32 = 3;
 
 /// Contains the shortcut and the path.
 /// Is used to store them and to pass them to a Cmd element.
 #[derive(Debug, Default)]
 pub struct ShortPath {
-    /// Shortcut entered by the user.
-    pub short: Option<String>,
     /// Path to the directory, or subpath of the directory corresponding to the shortcut.
     pub path: Option<String>,
+    /// Shortcut entered by the user.
+    pub short: Option<String>,
+}
+
+impl ShortPath {
+    /// Function to fill the shortcut, then the path.
+    fn push(self, value: String) -> Self {
+        match self.short {
+            None => Self {
+                short: Some(value),
+                ..self
+            },
+            Some(_) => Self {
+                path: Some(value),
+                ..self
+            },
+        }
+    }
 }
 
-/// `enum` to store the command to execute, and its arguments
+/// Contains the shortcuts and the path given to the `-add` command.
+/// The last value is the path if there are several, the others are the shortcuts.
 /// # Examples
 /// ```
-/// let cmd = Cmd::Get([Some(String::from("short")), Some(String::from("path"))]);
+/// use goto::commands::ShortsPath;
+/// let args = ShortsPath { values: vec![String::from("s1"), String::from("s2"), String::from("/path")], ..ShortsPath::default() };
+/// assert!(args.shorts() == ["s1", "s2"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct ShortsPath {
+    /// `true` if `--allow-missing` was given, to add a directory that doesn't exist (yet).
+    pub allow_missing: bool,
+    /// File of shortcuts chosen with `--global` or `--local`.
+    pub scope: Scope,
+    /// File of shortcuts given to `--to`, to add the directory to instead of the personal one (empty until the file is given).
+    pub to: Option<String>,
+    /// Values entered by the user, as typed.
+    pub values: Vec<String>,
+}
+
+impl ShortsPath {
+    /// Function to get the path (`None` if only the shortcut was given).
+    pub fn path(&self) -> Option<String> {
+        match self.values.as_slice() {
+            [] | [_] => None,
+            // The path is already formatted (see `AppendDefault`)
+            [.., path] => Some(escape_path(path)),
+        }
+    }
+
+    /// Function to get the shortcuts.
+    pub fn shorts(&self) -> &[String] {
+        match self.values.split_last() {
+            Some((_, shorts)) if !shorts.is_empty() => shorts,
+            _ => &self.values,
+        }
+    }
+}
+
+/// Contains an existing shortcut and a new one.
+/// Is used to pass them to the Cmd elements that create a shortcut from another one.
+#[derive(Debug, Default)]
+pub struct ShortPair {
+    /// New shortcut entered by the user.
+    pub new: Option<String>,
+    /// Existing shortcut entered by the user.
+    pub old: Option<String>,
+}
+
+impl ShortPair {
+    /// Function to fill the existing shortcut, then the new one.
+    fn push(self, value: String) -> Self {
+        match self.old {
+            None => Self {
+                old: Some(value),
+                ..self
+            },
+            Some(_) => Self {
+                new: Some(value),
+                ..self
+            },
+        }
+    }
+}
+
+/// File of shortcuts modified by `-add` and `-edit`.
+/// Is chosen with `--global` or `--local` (see `GlobalData::shared`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// The first shared file, to curate the shortcuts of a team.
+    Global,
+    /// The personal file, by default.
+    #[default]
+    Local,
+}
+
+impl Scope {
+    /// Function to get the flag of the scope, empty for the default one.
+    const fn flag(self) -> &'static str {
+        match self {
+            Self::Local => "",
+            Self::Global => " --global",
+        }
+    }
+
+    /// Function to read `--global` or `--local`.
+    /// # Returns
+    /// The scope, or `None` if the value is neither of them.
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "--global" => Some(Self::Global),
+            "--local" => Some(Self::Local),
+            _ => None,
+        }
+    }
+}
+
+/// Contains a `no_dirs` option and the values given to it.
+/// Is used for the options that don't need to modify the file of supported shortcuts (see `GlobalData`).
+/// # Examples
+/// ```
+/// use goto::commands::Opt;
+/// let opt = Opt { name: String::from("-rank"), values: vec![String::from("5")] };
+/// ```
+#[derive(Debug, Default)]
+pub struct Opt {
+    /// Name of the option, without its alias (e.g. `-noclear` instead of `!`).
+    pub name: String,
+    /// Values given to the option.
+    pub values: Vec<String>,
+}
+
+/// Trait to look for an option in the `no_dirs` arguments.
+pub trait HasOpt {
+    /// Lone method of the trait.
+    fn has(&self, name: &str) -> bool;
+}
+
+impl HasOpt for [Opt] {
+    fn has(&self, name: &str) -> bool {
+        self.iter().any(|opt| opt.name == name)
+    }
+}
+
+/// `enum` to store the command to execute, and its arguments.
+/// # Examples
+/// ```
+/// use goto::commands::{Cmd, ShortPath};
+/// let cmd = Cmd::Get(ShortPath { short: Some(String::from("short")), path: Some(String::from("path")) });
 /// ```
 /// # Note
 /// The command is used to get the path of a directory, to add a directory to the file of supported shortcuts, to edit the path of a directory, to remove a directory from the file of supported shortcuts, to delete a directory from the file of supported shortcuts, to decrement the usage of all directories, or to reset the usage of all directories to 0.
 #[derive(Debug)]
 pub enum Cmd {
-    /// Get the path of a directory.
-    Get(ShortPath),
-    /// Add a directory to the file of supported shortcuts.
-    Add(ShortPath),
-    /// Edit the path of a directory.
-    Edit(ShortPath),
-    /// Remove a directory from the file of supported shortcuts.
-    Rm(String),
+    /// Add a directory to the file of supported shortcuts (after the line of the given shortcut, if any).
+    Add(ShortsPath, Option<String>),
+    /// Multiply the usage of all directories by a factor between 0 and 1 (`AGE_FACTOR` if not given), rounding down.
+    Age(Option<f64>),
+    /// Add a new line with the path of a shortcut, under a new shortcut.
+    Clone(ShortPair),
+    /// Decrement the usage of all directories, or only of the directory of the shortcut if given, by the given amount.
+    Decr(Option<String>, Option<u32>),
     /// Delete a directory from the file of supported shortcuts.
     Del(String),
-    /// Decrement the usage of all directories.
-    Decr(u32),
+    /// Edit the path of a directory (in the file of the scope).
+    Edit(ShortPath, Scope),
+    /// Stop incrementing the priority of a directory when its shortcut is used.
+    Freeze(String),
+    /// Get the path of a directory.
+    Get(ShortPath),
+    /// Change the path of a directory, keeping its shortcuts and its priority.
+    Move(String, Option<String>),
+    /// Remove the directories that don't exist anymore (only the ones marked as broken if the first is `true`, and only listed if the second is `true`).
+    Prune(bool, bool),
+    /// Rename a shortcut, wherever it is in the file of supported shortcuts.
+    Replace(ShortPair),
     /// Reset the usage of all directories to 0.
     Reset,
+    /// Remove shortcuts from the file of supported shortcuts.
+    Rm(Vec<String>),
+    /// Set the priority of the directory of a shortcut.
+    Set(String, Option<u32>),
+    /// Increment again the priority of a directory when its shortcut is used.
+    Unfreeze(String),
 }
 
 impl fmt::Display for Cmd {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         let val = match self {
             Self::Get(ShortPath { short, path }) => {
                 format!(
                     "<goto {} {}>",
-                    &short.clone().unwrap_or_default(),
-                    &path.clone().unwrap_or_default()
+                    short.clone().unwrap_or_default(),
+                    path.clone().unwrap_or_default()
                 )
             }
-            Self::Add(ShortPath { short, path }) => {
+            Self::Add(
+                ShortsPath {
+                    values, to, scope, ..
+                },
+                after,
+            ) => {
                 format!(
-                    "<add {} {}>",
-                    &short.clone().unwrap_or_default(),
-                    &path.clone().unwrap_or_default()
+                    "<add {}{}{}{}>",
+                    values.join(" "),
+                    scope.flag(),
+                    after
+                        .as_ref()
+                        .map(|af| format!(" --after {af}"))
+                        .unwrap_or_default(),
+                    to.as_ref()
+                        .map(|file| format!(" --to {file}"))
+                        .unwrap_or_default()
                 )
             }
-            Self::Edit(ShortPath { short, path }) => {
+            Self::Edit(ShortPath { short, path }, scope) => {
                 format!(
-                    "<edit {} {}>",
-                    &short.clone().unwrap_or_default(),
-                    &path.clone().unwrap_or_default()
+                    "<edit {} {}{}>",
+                    short.clone().unwrap_or_default(),
+                    path.clone().unwrap_or_default(),
+                    scope.flag()
                 )
             }
-            Self::Rm(short) => format!("<rm {short}>"),
+            Self::Rm(shorts) => format!("<rm {}>", shorts.join(" ")),
             Self::Del(path) => format!("<del {path}>"),
-            Self::Decr(val) => format!("<decr {val}>"),
+            Self::Freeze(short) => format!("<freeze-priority {short}>"),
+            Self::Unfreeze(short) => format!("<unfreeze-priority {short}>"),
+            Self::Set(short, priory) => format!(
+                "<set {short} {}>",
+                priory.map(|pr| pr.to_string()).unwrap_or_default()
+            ),
+            Self::Move(old, new) => format!("<move {old} {}>", new.clone().unwrap_or_default()),
+            Self::Decr(None, decr) => format!(
+                "<decr {}>",
+                decr.map(|val| val.to_string()).unwrap_or_default()
+            ),
+            Self::Decr(Some(short), decr) => format!(
+                "<decr {short} {}>",
+                decr.map(|val| val.to_string()).unwrap_or_default()
+            ),
             Self::Reset => "<reset>".to_owned(),
+            Self::Age(factor) => format!("<age {}>", factor.unwrap_or(AGE_FACTOR)),
+            Self::Prune(broken_only, dry_run) => format!(
+                "<prune{}{}>",
+                if *broken_only { " --broken" } else { "" },
+                if *dry_run { " --dry-run" } else { "" }
+            ),
+            Self::Clone(ShortPair { old, new }) => {
+                format!(
+                    "<clone {} {}>",
+                    old.clone().unwrap_or_default(),
+                    new.clone().unwrap_or_default()
+                )
+            }
+            Self::Replace(ShortPair { old, new }) => {
+                format!(
+                    "<replace-shortcut {} {}>",
+                    old.clone().unwrap_or_default(),
+                    new.clone().unwrap_or_default()
+                )
+            }
         };
-        write!(fmt, "{val}")
+        write!(f, "{val}")
     }
 }
 
@@ -82,76 +299,187 @@ impl Cmd {
     /// Implement the `append` method for the `Cmd` enum.
     /// # Arguments
     /// * `value` - The value to append to the command.
-    /// # Panics
-    /// If the command is already full of all its arguments.
+    /// # Errors
+    /// If the value is a relative path, and the current directory can't be accessed (see `NoCurrentDir`).
     /// # Examples
     /// ```
-    /// let mut cmd = Cmd::Get([None, None]);
-    /// cmd.append(String::from("short"));
-    /// cmd.append(String::from("path"));
+    /// use goto::commands::{Cmd, ShortPath};
+    /// let mut cmd = Cmd::Get(ShortPath::default());
+    /// cmd.append(String::from("short")).unwrap_or_default();
+    /// cmd.append(String::from("path")).unwrap_or_default();
     /// ```
-    ///
-    pub fn append(&mut self, value: String) {
+    /// # Note
+    /// An error is printed if the command is already full of all its arguments.
+    pub fn append(&mut self, value: String) -> Result<(), NoCurrentDir> {
+        let mut result = Ok(());
         match mem::take(self) {
             Self::Reset => user_error!("The <-reset> option takes no arguments."),
-            Self::Decr(0) => {
-                *self = Self::Decr(value.parse::<u32>().unwrap_or_else(|_| {
-                    user_error!("The value of <-decrement> must be an integer.");
-                    0
-                }));
+            Self::Prune(false, dry_run) if value == "--broken" => {
+                *self = Self::Prune(true, dry_run);
             }
+            Self::Prune(broken_only, false) if value == "--dry-run" => {
+                *self = Self::Prune(broken_only, true);
+            }
+            // The values are read by position: with two values, the first one is the shortcut,
+            // and a lone value is the decrement of all the directories (see `append_default`)
+            Self::Decr(None, None) => *self = Self::Decr(Some(value), None),
+            Self::Decr(Some(short), None) => {
+                *self = Self::Decr(Some(short), Some(parse_decr(&value)));
+            }
+            Self::Age(None) => *self = Self::Age(Some(parse_age(&value))),
 
-            Self::Get(ShortPath { short: None, .. }) => {
-                *self = Self::Get(ShortPath {
-                    short: Some(value),
-                    path: None,
-                });
+            Self::Get(short_path @ ShortPath { path: None, .. }) => {
+                *self = Self::Get(short_path.push(value));
             }
-            Self::Get(ShortPath { short, path: None }) => {
-                *self = Self::Get(ShortPath {
-                    short,
-                    path: Some(value),
-                });
+
+            Self::Add(shorts_path, after) => {
+                let (cmd, res) = Self::append_add(shorts_path, after, value);
+                *self = cmd;
+                result = res;
             }
 
-            Self::Add(ShortPath { short: None, .. }) => {
-                *self = Self::Add(ShortPath {
-                    short: Some(value),
-                    path: None,
-                });
+            Self::Edit(short_path, _) if Scope::parse(&value).is_some() => {
+                *self = Self::Edit(short_path, Scope::parse(&value).unwrap_or_default());
+            }
+            Self::Edit(short_path @ ShortPath { short: None, .. }, scope) => {
+                *self = Self::Edit(short_path.push(value), scope);
             }
-            Self::Add(ShortPath { short, path: None }) => {
-                *self = Self::Add(ShortPath {
-                    short,
-                    path: Some(std_path(&value)),
-                });
+            Self::Edit(ShortPath { short, path: None }, scope) => {
+                match std_path(&expand_path(&value)) {
+                    Ok(path) => {
+                        *self = Self::Edit(
+                            ShortPath {
+                                short,
+                                path: Some(escape_path(&file_dir(&path))),
+                            },
+                            scope,
+                        );
+                    }
+                    Err(er) => result = Err(er),
+                }
             }
 
-            Self::Edit(ShortPath { short: None, .. }) => {
-                *self = Self::Edit(ShortPath {
-                    short: Some(value),
-                    path: None,
-                });
+            Self::Rm(mut shorts) => {
+                shorts.push(value);
+                *self = Self::Rm(shorts);
             }
-            Self::Edit(ShortPath { short, path: None }) => {
-                *self = Self::Edit(ShortPath {
-                    short,
-                    path: Some(std_path(&value)),
-                });
+            Self::Del(st) if st.is_empty() => *self = Self::Del(escape_path(&value)),
+            Self::Freeze(st) if st.is_empty() => *self = Self::Freeze(value),
+            Self::Unfreeze(st) if st.is_empty() => *self = Self::Unfreeze(value),
+            Self::Set(st, None) if st.is_empty() => *self = Self::Set(value, None),
+            Self::Set(short, None) => {
+                let priory = value.parse::<u32>().map_or_else(
+                    |er| {
+                        user_error!(
+                            "The value of <-set> must be an integer between 0 and {}: {er}",
+                            u32::MAX
+                        );
+                        None
+                    },
+                    Some,
+                );
+                *self = Self::Set(short, priory);
             }
+            Self::Move(st, None) if st.is_empty() => match std_path(&expand_path(&value)) {
+                Ok(old) => *self = Self::Move(escape_path(&old), None),
+                Err(er) => result = Err(er),
+            },
+            Self::Move(old, None) => match std_path(&expand_path(&value)) {
+                Ok(new) => *self = Self::Move(old, Some(escape_path(&new))),
+                Err(er) => result = Err(er),
+            },
 
-            Self::Rm(st) if st.is_empty() => *self = Self::Rm(value),
-            Self::Del(st) if st.is_empty() => *self = Self::Del(value),
+            Self::Clone(pair @ ShortPair { new: None, .. }) => {
+                *self = Self::Clone(pair.push(value));
+            }
+            Self::Replace(pair @ ShortPair { new: None, .. }) => {
+                *self = Self::Replace(pair.push(value));
+            }
 
             Self::Get(_)
-            | Self::Edit(_)
-            | Self::Add(_)
-            | Self::Rm(_)
+            | Self::Edit(..)
             | Self::Del(_)
-            | Self::Decr(_) => {
+            | Self::Decr(..)
+            | Self::Age(_)
+            | Self::Clone(_)
+            | Self::Replace(_)
+            | Self::Prune(..)
+            | Self::Freeze(_)
+            | Self::Unfreeze(_)
+            | Self::Set(..)
+            | Self::Move(..) => {
                 user_error!("Too many arguments for <{self}> command.");
             }
         }
+        result
+    }
+
+    /// Append a value to the `-add` command (see `append`).
+    /// # Returns
+    /// The command with the value, and the error of the path given to `--to` if any.
+    fn append_add(
+        mut shorts_path: ShortsPath,
+        mut after: Option<String>,
+        value: String,
+    ) -> (Self, Result<(), NoCurrentDir>) {
+        let mut result = Ok(());
+        if value == "--allow-missing" {
+            shorts_path.allow_missing = true;
+        } else if after.is_none() && value == "--after" {
+            after = Some(String::new());
+        } else if after.as_ref().is_some_and(String::is_empty) {
+            after = Some(value);
+        } else if shorts_path.to.is_none() && value == "--to" {
+            shorts_path.to = Some(String::new());
+        } else if shorts_path.to.as_ref().is_some_and(String::is_empty) {
+            match std_path(&expand_path(&value)) {
+                Ok(to) => shorts_path.to = Some(to),
+                Err(er) => result = Err(er),
+            }
+        } else {
+            match Scope::parse(&value) {
+                Some(scope) => shorts_path.scope = scope,
+                None => shorts_path.values.push(value),
+            }
+        }
+        (Self::Add(shorts_path, after), result)
+    }
+
+    /// Resolve the symbolic links in the path given to `-add` or `-edit` (see `canonical_path`).
+    /// # Note
+    /// Must be called once all the values are given (see `AppendDefault`).
+    pub fn canonicalize(&mut self) {
+        match self {
+            Self::Add(ShortsPath { values, .. }, _) if values.len() >= 2 => {
+                if let Some(path) = values.last_mut() {
+                    *path = canonical_path(path);
+                }
+            }
+            Self::Edit(
+                ShortPath {
+                    path: Some(path), ..
+                },
+                _,
+            ) => {
+                let canon = canonical_path(&unescape_path(path));
+                *path = escape_path(&std_path(&canon).unwrap_or(canon));
+            }
+            Self::Get(_)
+            | Self::Add(..)
+            | Self::Edit(..)
+            | Self::Rm(_)
+            | Self::Del(_)
+            | Self::Decr(..)
+            | Self::Reset
+            | Self::Age(_)
+            | Self::Clone(_)
+            | Self::Replace(_)
+            | Self::Prune(..)
+            | Self::Freeze(_)
+            | Self::Unfreeze(_)
+            | Self::Set(..)
+            | Self::Move(..) => (),
+        }
     }
 }
 
@@ -163,15 +491,23 @@ pub trait ToCmd {
 
 impl ToCmd for str {
     fn to_cmd(&self) -> Cmd {
-        #[allow(clippy::print_stderr)]
+        #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
         match self {
             "-get" => Cmd::Get(ShortPath::default()),
-            "-add" => Cmd::Add(ShortPath::default()),
-            "-edit" => Cmd::Edit(ShortPath::default()),
-            "-remove" => Cmd::Rm(String::new()),
+            "-add" => Cmd::Add(ShortsPath::default(), None),
+            "-edit" => Cmd::Edit(ShortPath::default(), Scope::default()),
+            "-remove" => Cmd::Rm(vec![]),
             "-reset" => Cmd::Reset,
             "-delete" => Cmd::Del(String::new()),
-            "-decrement" => Cmd::Decr(0),
+            "-decrement" => Cmd::Decr(None, None),
+            "-age" => Cmd::Age(None),
+            "-clone" => Cmd::Clone(ShortPair::default()),
+            "-replace-shortcut" => Cmd::Replace(ShortPair::default()),
+            "-prune" => Cmd::Prune(false, false),
+            "-freeze-priority" => Cmd::Freeze(String::new()),
+            "-unfreeze-priority" => Cmd::Unfreeze(String::new()),
+            "-set" => Cmd::Set(String::new(), None),
+            "-move" => Cmd::Move(String::new(), None),
             _ => {
                 eprintln!("[Internal error] Trying to convert <{self}> to valid command.");
                 Cmd::default()
@@ -186,6 +522,153 @@ impl ToCmd for String {
     }
 }
 
+/// Trait to append a default value to a command.
+///
+/// The default value is the current directory, and is `None` if it can't be accessed (see `NoCurrentDir`).
+/// If the path of `-add` was given, it is expanded instead (see `expand_path`).
+pub trait AppendDefault {
+    /// Lone method of the trait.
+    /// # Errors
+    /// If the current directory is needed, but can't be accessed.
+    fn append_default(self, value: Option<&str>) -> Result<(), NoCurrentDir>;
+}
+
+impl AppendDefault for Option<&mut Cmd> {
+    fn append_default(self, value: Option<&str>) -> Result<(), NoCurrentDir> {
+        if let Some(cmd) = self {
+            match cmd {
+                Cmd::Add(ShortsPath { values, .. }, _) if values.len() < 2 => {
+                    let Some(here) = value else {
+                        return Err(NoCurrentDir);
+                    };
+                    if values.is_empty() {
+                        values.push(path2dir(here));
+                    }
+                    values.push(here.to_owned());
+                }
+                // The path typed by the user is only known once all the values are given
+                Cmd::Add(ShortsPath { values, .. }, _) => {
+                    if let Some(path) = values.last_mut() {
+                        match std_path(&expand_path(path)) {
+                            Ok(formatted) => *path = file_dir(&formatted),
+                            Err(er) => return Err(er),
+                        }
+                    }
+                }
+                Cmd::Edit(
+                    ShortPath {
+                        short: None,
+                        path: None,
+                    },
+                    _,
+                ) => {
+                    let Some(here) = value else {
+                        return Err(NoCurrentDir);
+                    };
+                    return match cmd.append(path2dir(here)) {
+                        Ok(()) => cmd.append(here.to_owned()),
+                        Err(er) => Err(er),
+                    };
+                }
+                Cmd::Edit(ShortPath { path: None, .. }, _) => {
+                    return value.map_or(Err(NoCurrentDir), |here| cmd.append(here.to_owned()));
+                }
+
+                // A lone value is the decrement of all the directories, and a lone shortcut misses its decrement (see `read`)
+                Cmd::Decr(opt_short, decr @ None) => {
+                    if let Some(amount) =
+                        opt_short.as_deref().and_then(|val| val.parse::<u32>().ok())
+                    {
+                        *opt_short = None;
+                        *decr = Some(amount);
+                    }
+                }
+
+                Cmd::Get(_)
+                | Cmd::Edit(..)
+                | Cmd::Rm(_)
+                | Cmd::Del(_)
+                | Cmd::Decr(..)
+                | Cmd::Reset
+                | Cmd::Age(_)
+                | Cmd::Clone(_)
+                | Cmd::Replace(_)
+                | Cmd::Prune(..)
+                | Cmd::Freeze(_)
+                | Cmd::Unfreeze(_)
+                | Cmd::Set(..)
+                | Cmd::Move(..) => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lock on a file of `goto`, released when dropped (see `lock`).
+#[derive(Debug)]
+pub struct Lock {
+    /// Path of the lock file.
+    path: String,
+    /// Content of the lock file, to identify its owner.
+    token: String,
+}
+
+/// Error returned when the current directory is needed but can't be accessed.
+/// # Note
+/// This avoids building paths relative to an empty directory, and storing them in the files.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NoCurrentDir;
+
+#[expect(
+    clippy::missing_trait_methods,
+    reason = "`Drop` has no other method to implement"
+)]
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // The lock file may have been removed as stale, and taken by another process
+        if fs::read_to_string(&self.path).is_ok_and(|owner| owner == self.token) {
+            drop(fs::remove_file(&self.path));
+        }
+    }
+}
+
+impl fmt::Display for NoCurrentDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "Unable to access the current directory (it may have been deleted). Please give an absolute path, or go to an existing directory")
+    }
+}
+
+/// Function to parse the value of the `-age` command.
+/// # Arguments
+/// * `value` - The value given by the user.
+/// # Returns
+/// The factor, or 1 (the priorities are unchanged) if the value is not a number between 0 and 1.
+fn parse_age(value: &str) -> f64 {
+    let unchanged: f64 = 1.0;
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|factor| (f64::MIN_POSITIVE..unchanged).contains(factor))
+        .unwrap_or_else(|| {
+            user_error!(
+                "The value of <-age> must be a number between 0 and 1 (excluded), e.g. 0.9"
+            );
+            unchanged
+        })
+}
+
+/// Function to parse the value of the `-decrement` command.
+/// # Arguments
+/// * `value` - The value given by the user.
+/// # Returns
+/// The decrement, or 0 if the value is not an integer.
+fn parse_decr(value: &str) -> u32 {
+    value.parse::<u32>().unwrap_or_else(|_| {
+        user_error!("The value of <-decrement> must be an integer.");
+        0
+    })
+}
+
 /// Function to get the directory from a path.
 /// # Arguments
 /// * `path` - The path to get the directory from.
@@ -193,106 +676,670 @@ impl ToCmd for String {
 /// The directory.
 /// # Examples
 /// ```
+/// use goto::commands::path2dir;
 /// let dir = path2dir("C:/Users/username/Documents");
 /// assert_eq!(dir, "Documents");
 /// ```
-fn path2dir(path: &str) -> String {
+pub fn path2dir(path: &str) -> String {
     path.split('/')
-        .last()
+        .next_back()
         .internal_error("The path is not valid", None)
         .split('\\')
-        .last()
+        .next_back()
         .internal_error("The path is still not valid", None)
         .to_owned()
 }
 
-/// Function to format a path
+/// Function to replace the content of a file, without leaving it truncated if the program is interrupted.
+/// # Arguments
+/// * `fpath` - The path of the file
+/// * `data` - The new content of the file
+/// # Errors
+/// If the temporary file can't be written or renamed.
+/// # Note
+/// The content is written in `fpath.tmp`, which is then renamed to `fpath` (this is atomic in the same directory, both on Linux and Windows).
+/// If the program is interrupted before the rename, `fpath` is left untouched.
+pub fn write_atomic(fpath: &str, data: &str) -> io::Result<()> {
+    let tmp = format!("{fpath}.tmp");
+    match fs::write(&tmp, data) {
+        Ok(()) => fs::rename(&tmp, fpath),
+        Err(er) => Err(er),
+    }
+}
+
+/// Function to get a token unique to a lock, written in the lock file (see `Lock`).
+/// # Returns
+/// The process id, the time and the number of locks already taken by the process.
+fn lock_token() -> String {
+    static COUNT: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+    let nanos = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map_or(0, |dur| dur.as_nanos());
+    format!(
+        "{}-{nanos}-{}",
+        process::id(),
+        COUNT.fetch_add(1, atomic::Ordering::Relaxed)
+    )
+}
+
+/// Function to lock a file, so that two processes don't modify it at the same time.
+/// # Arguments
+/// * `fpath` - The path of the file
+/// # Returns
+/// The lock, to keep until the file is written.
+/// # Errors
+/// If the lock file can't be created, or if it is still taken after `LOCK_RETRIES` stale locks were removed: the file mustn't be written then.
+/// # Example
+/// ```no_run
+/// use goto::commands::{lock, write_atomic};
+/// use std::fs;
+/// if let Ok(lock) = lock("lib/dirs.csv") {
+///     let content = fs::read_to_string("lib/dirs.csv").unwrap_or_default();
+///     write_atomic("lib/dirs.csv", &content).unwrap_or_default();
+///     drop(lock);
+/// }
+/// ```
+/// # Note
+/// The lock is the file `fpath.lock`, created only if it doesn't exist, and containing a token unique to the lock (see `lock_token`).
+/// If it exists, the creation is retried with a growing delay.
+/// If the same token stays in the lock file for `LOCK_TIMEOUT`, the lock is removed as stale (its process was probably killed).
+pub fn lock(fpath: &str) -> io::Result<Lock> {
+    let path = format!("{fpath}.lock");
+    let token = lock_token();
+    let mut delay = 1;
+    let mut waited = 0;
+    let mut owner = None;
+    let mut retries: u32 = 0;
+    loop {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                return match file.write_all(token.as_bytes()) {
+                    Ok(()) => Ok(Lock { path, token }),
+                    Err(er) => {
+                        drop(fs::remove_file(&path));
+                        Err(er)
+                    }
+                };
+            }
+            Err(er) if er.kind() == io::ErrorKind::AlreadyExists => {
+                let current = fs::read_to_string(&path).ok();
+                if current == owner && waited >= LOCK_TIMEOUT {
+                    if retries >= LOCK_RETRIES {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("{path} is still taken by another process"),
+                        ));
+                    }
+                    file_error!("{fpath} was locked for more than {LOCK_TIMEOUT} ms: removing the stale lock {path}");
+                    // The lock is only removed if it still belongs to the stale owner
+                    if fs::read_to_string(&path).ok() == owner {
+                        drop(fs::remove_file(&path));
+                    }
+                    retries = retries.saturating_add(1);
+                    waited = 0;
+                    continue;
+                }
+                if current != owner {
+                    // The lock was taken by another process: its own delay starts
+                    owner = current;
+                    waited = 0;
+                }
+                thread::sleep(time::Duration::from_millis(delay));
+                waited = waited.saturating_add(delay);
+                delay = delay.saturating_mul(2).min(100);
+            }
+            Err(er) => return Err(er),
+        }
+    }
+}
+
+/// Function to get the current directory.
+/// # Returns
+/// The current directory, or `None` if it can't be accessed (e.g. if it was deleted).
+pub fn current_dir() -> Option<String> {
+    env::current_dir()
+        .ok()
+        .as_deref()
+        .and_then(Path::to_str)
+        .map(str::to_owned)
+}
+
+/// Function to check if a path is a network path (e.g. `\\server\share\dir` or `//server/share/dir`).
+/// # Arguments
+/// * `path` - The path to check.
+/// # Returns
+/// `true` if the path is a UNC path, i.e. starts with exactly two separators, followed by a server and a share.
+/// # Note
+/// A unix path like `//home` has no share, and is thus the same as `/home`.
+pub fn is_unc(path: &str) -> bool {
+    path.strip_prefix("\\\\")
+        .or_else(|| path.strip_prefix("//"))
+        .is_some_and(|rest| {
+            !rest.starts_with(['/', '\\'])
+                && rest
+                    .split(['/', '\\'])
+                    .filter(|part| !part.is_empty())
+                    .count()
+                    >= 2
+        })
+}
+
+/// Function to escape the semicolons of a path, so that it can be stored in the files of `goto`.
+/// # Arguments
+/// * `path` - The path to escape.
+/// # Returns
+/// The path with `;` replaced by `\;`, and `\` by `\\` (so that a path ending with `\` doesn't escape the next `;`).
+/// # Example
+/// ```
+/// use goto::commands::escape_path;
+/// assert!(escape_path("/home/a;b/c") == "/home/a\\;b/c");
+/// ```
+pub fn escape_path(path: &str) -> String {
+    path.replace('\\', "\\\\").replace(';', "\\;")
+}
+
+/// Function to get back a path escaped with `escape_path`.
+/// # Arguments
+/// * `path` - The path, as stored in the files of `goto`.
+/// # Returns
+/// The path with `\;` replaced by `;`, and `\\` by `\`.
+pub fn unescape_path(path: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = path.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unescaped.push(chars.next().unwrap_or(char)),
+            _ => unescaped.push(char),
+        }
+    }
+    unescaped
+}
+
+/// Function to split a line of the files of `goto` on the semicolons that aren't escaped (see `escape_path`).
+/// # Arguments
+/// * `line` - The line to split.
+/// # Returns
+/// The fields of the line, still escaped.
+/// # Example
+/// ```
+/// use goto::commands::split_line;
+/// assert!(split_line("/home/a\\;b/c;f;1") == vec!["/home/a\\;b/c", "f", "1"]);
+/// ```
+/// # Note
+/// A `\` escapes the next character, so `\\;` is an escaped `\` followed by a separator.
+pub fn split_line(line: &str) -> Vec<&str> {
+    let mut fields = vec![];
+    let mut start: usize = 0;
+    let mut escaped = false;
+    for (idx, char) in line.char_indices() {
+        if char == ';' && !escaped {
+            fields.push(line.get(start..idx).unwrap_or_default());
+            start = idx.saturating_add(1);
+        }
+        escaped = char == '\\' && !escaped;
+    }
+    fields.push(line.get(start..).unwrap_or_default());
+    fields
+}
+
+/// Function to get the directory of a file, for `-add` and `-edit`.
+/// # Arguments
+/// * `path` - The path given by the user, formatted with `std_path`
+/// # Returns
+/// The parent directory if the path is a file (with a note), else the path unchanged.
+/// # Example
+/// ```no_run
+/// use goto::commands::file_dir;
+/// assert!(file_dir("/home/user/.config/app/config.toml") == "/home/user/.config/app");
+/// ```
+#[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+pub fn file_dir(path: &str) -> String {
+    let file = Path::new(path);
+    file.parent().filter(|_| file.is_file()).map_or_else(
+        || path.to_owned(),
+        |parent| {
+            let dir = parent.to_string_lossy().into_owned();
+            eprintln!("{path} is a file: its directory {dir} is used instead");
+            dir
+        },
+    )
+}
+
+/// Function to resolve the symbolic links of a path.
+/// # Arguments
+/// * `path` - The path to resolve, formatted with `std_path`
+/// # Returns
+/// The canonical path if the path exists, else the path unchanged (e.g. for a directory that will be created later).
+/// # Note
+/// The `\\?\` prefix added by Windows is removed, so that the path is still a valid path for the shell.
+pub fn canonical_path(path: &str) -> String {
+    fs::canonicalize(path).map_or_else(
+        |_| path.to_owned(),
+        |canon| {
+            let canon_path = canon.display().to_string();
+            canon_path.strip_prefix("\\\\?\\UNC\\").map_or_else(
+                || canon_path.trim_start_matches("\\\\?\\").to_owned(),
+                |rest| format!("\\\\{rest}"),
+            )
+        },
+    )
+}
+
+/// Function to expand the home directory and the environment variables in a path.
+/// # Arguments
+/// * `path` - The path typed by the user
+/// # Returns
+/// The path with a leading `~` replaced by the home directory (`HOME`, or `USERPROFILE` on Windows), and the `$VAR` (or `%VAR%` on Windows) replaced by their values.
+/// # Example
+/// ```no_run
+/// use goto::commands::expand_path;
+/// assert!(expand_path("~/projects/$USER") == "/home/user/projects/user");
+/// ```
+/// # Note
+/// `~user` is kept as is, and so are the variables that are not defined (with an error).
+pub fn expand_path(path: &str) -> String {
+    let windows = cfg!(target_os = "windows");
+    let home = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            format!(
+                "{}{rest}",
+                env_var(if windows { "USERPROFILE" } else { "HOME" }, "~")
+            )
+        }
+        _ => path.to_owned(),
+    };
+    let mut expanded = String::new();
+    let mut chars = home.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '$' => {
+                let name = var_name(&mut chars);
+                if name.is_empty() {
+                    expanded.push('$');
+                } else {
+                    expanded.push_str(&env_var(&name, &format!("${name}")));
+                }
+            }
+            '%' if windows => {
+                let name = var_name(&mut chars);
+                if !name.is_empty() && chars.peek() == Some(&'%') {
+                    chars.next();
+                    expanded.push_str(&env_var(&name, &format!("%{name}%")));
+                } else {
+                    expanded.push('%');
+                    expanded.push_str(&name);
+                }
+            }
+            _ => expanded.push(char),
+        }
+    }
+    expanded
+}
+
+/// Function to read the name of an environment variable in a path.
+/// # Arguments
+/// * `chars` - The characters of the path, right after the `$` or the `%`
+/// # Returns
+/// The name of the variable (only letters, digits and `_`), possibly empty.
+fn var_name(chars: &mut iter::Peekable<str::Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&char) = chars.peek() {
+        if !char.is_ascii_alphanumeric() && char != '_' {
+            break;
+        }
+        name.push(char);
+        chars.next();
+    }
+    name
+}
+
+/// Function to get the value of an environment variable used in a path.
+/// # Arguments
+/// * `name` - The name of the variable
+/// * `raw` - The text to keep in the path if the variable is not defined
+/// # Returns
+/// The value of the variable, or `raw` with an error if it is not defined.
+fn env_var(name: &str, raw: &str) -> String {
+    env::var(name).unwrap_or_else(|_| {
+        user_error!("Environment variable {name} is not defined, {raw} is kept as is");
+        raw.to_owned()
+    })
+}
+
+/// Function to format a path.
 /// # Arguments
 /// * `path` - The path to format
 /// # Returns
-/// The formatted path
+/// The formatted path.
+/// # Errors
+/// If the path is relative and the current directory can't be accessed (see `NoCurrentDir`).
+/// # Note
+/// Only the drive letter of the windows paths is lowercased: the other paths keep their case.
+/// Network paths (see `is_unc`) keep their leading `//`.
+/// The `.` and `..` of the path are resolved (see `resolve_dots`), and the trailing `/` is removed, except for the root.
 /// # Example   
 /// ```
-/// assert!(std_path(&Some(String::from("D:/Windows\\PeRso"))) == "d:/Windows/PeRso");
+/// use goto::commands::std_path;
+/// assert!(std_path("D:/Windows\\PeRso").is_ok_and(|path| path == "d:/Windows/PeRso"));
 /// ```
 ///
-pub fn std_path(ipath: &str) -> String {
+pub fn std_path(ipath: &str) -> Result<String, NoCurrentDir> {
+    std_path_in(ipath, current_dir)
+}
+
+/// Function to format a path (see `std_path`).
+/// # Arguments
+/// * `ipath` - The path to format.
+/// * `here` - The function to get the current directory, only called if the path is relative.
+/// # Returns
+/// The formatted path.
+/// # Errors
+/// If the path is relative and `here` returns `None`.
+fn std_path_in(ipath: &str, here: fn() -> Option<String>) -> Result<String, NoCurrentDir> {
     let mut path = ipath.to_owned().replace('\\', "/");
+    if is_unc(&path) {
+        let rest = path
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        return Ok(format!("//{rest}"));
+    }
+    let absolute = path.starts_with('/') || path.chars().nth(1) == Some(':');
+    if !absolute {
+        match here() {
+            Some(dir) => path = format!("{}/{path}", dir.replace('\\', "/")),
+            None => return Err(NoCurrentDir),
+        }
+    }
+    path = resolve_dots(&path);
 
-    let here = env::current_dir()
-        .user_error("Couldn't access current path", None)
-        .to_str()
-        .user_error("Error while casting current path to str", None)
-        .to_owned()
-        .replace('\\', "/");
-    let last_slash = here
-        .rfind('/')
-        .user_error("Expected a slash in the path", None);
-    let (father, _) = here.split_at(last_slash);
-
-    path = path.replace("..", father);
-    match path.chars().next() {
-        None => path = here,
-        Some('.') => path = format!("{here}{}", &path.get(1..).unwrap_or_default()),
-        Some('/') => (),
-        _ if path.chars().nth(1) == Some(':') => (),
-        _ => path = format!("{here}/{path}"),
-    }
-
-    let mut to_lower = true;
-    let mut res = path
-        .chars()
-        .map(|char| match char {
-            '\\' => {
-                to_lower = false;
-                '/'
-            }
-            ':' | '/' if to_lower => {
-                to_lower = false;
-                char
+    // Only the drive letter is lowercased, as the unix paths are case-sensitive
+    let mut chars = path.chars();
+    Ok(match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => {
+            format!("{}:{}", drive.to_ascii_lowercase(), chars.as_str())
+        }
+        _ => path,
+    })
+}
+
+/// Function to resolve the `.` and `..` of an absolute path.
+/// # Arguments
+/// * `path` - An absolute path, with `/` as separators
+/// # Returns
+/// The path without `.`, `..` and empty components, each `..` going up one directory.
+/// # Note
+/// The root (`/` or the drive, e.g. `c:`) is kept, even with too many `..`.
+/// A drive-relative path (e.g. `c:foo`, relative to the current directory of the drive, which is unknown) is read from the root of the drive, like `dos2unix` does.
+/// # Example
+/// ```ignore
+/// assert!(resolve_dots("/home/user/./a/../../b/") == "/home/b");
+/// assert!(resolve_dots("c:/..") == "c:/");
+/// assert!(resolve_dots("c:foo") == "c:/foo");
+/// ```
+fn resolve_dots(path: &str) -> String {
+    let mut components = path.split('/');
+    let first = components.next().unwrap_or_default();
+    let (root, relative) = match first.char_indices().nth(2) {
+        Some((idx, _)) if first.chars().nth(1) == Some(':') => first.split_at(idx),
+        _ => (first, ""),
+    };
+    let mut resolved: Vec<&str> = vec![];
+    for component in iter::once(relative).chain(components) {
+        match component {
+            "" | "." => (),
+            ".." => {
+                resolved.pop();
             }
-            _ if to_lower => char.to_ascii_lowercase(),
-            _ => char,
-        })
-        .collect::<String>();
-    if res.ends_with('/') {
-        res.pop();
+            _ => resolved.push(component),
+        }
     }
-    res
+    format!("{root}/{}", resolved.join("/"))
 }
 
-/// Trait to append a default value to a command.
-pub trait AppendDefault {
-    /// Lone method of the trait.
-    fn append_default(self, value: &str);
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl AppendDefault for Option<&mut Cmd> {
-    fn append_default(self, value: &str) {
-        if let Some(cmd) = self {
-            match cmd {
-                Cmd::Add(ShortPath {
-                    short: None,
-                    path: None,
-                })
-                | Cmd::Edit(ShortPath {
-                    short: None,
-                    path: None,
-                }) => {
-                    cmd.append(path2dir(value));
-                    cmd.append(value.to_owned());
+    /// Function to format a path, empty if the current directory can't be accessed.
+    fn std(path: &str) -> String {
+        std_path(path).unwrap_or_default()
+    }
+
+    /// Function to build a command from its values, as `parse_args` does.
+    fn cmd(name: &str, values: &[&str]) -> Result<String, NoCurrentDir> {
+        let mut command = name.to_cmd();
+        values
+            .iter()
+            .try_for_each(|value| command.append(String::from(*value)))
+            .map(|()| command.to_string())
+    }
+
+    /// Function to get a path in the temporary directory, unique to the test.
+    fn temp_file(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("goto-{}-{name}", process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn lock_is_exclusive() {
+        let fpath = temp_file("lock-count");
+        write_atomic(&fpath, "0").unwrap_or_default();
+        let increments: u32 = 25;
+        let threads = iter::repeat_with(|| {
+            let thread_path = fpath.clone();
+            thread::spawn(move || {
+                for _ in 0..increments {
+                    let guard = lock(&thread_path);
+                    assert!(guard.is_ok());
+                    let count = fs::read_to_string(&thread_path)
+                        .ok()
+                        .and_then(|content| content.parse::<u32>().ok())
+                        .unwrap_or_default();
+                    write_atomic(&thread_path, &count.saturating_add(1).to_string())
+                        .unwrap_or_default();
+                    drop(guard);
                 }
-                Cmd::Add(ShortPath { path: None, .. })
-                | Cmd::Edit(ShortPath { path: None, .. }) => cmd.append(value.to_owned()),
+            })
+        })
+        .take(8)
+        .collect::<Vec<_>>();
+        for handle in threads {
+            assert_eq!(handle.join().ok(), Some(()));
+        }
+        assert_eq!(fs::read_to_string(&fpath).unwrap_or_default(), "200");
+        assert!(!Path::new(&format!("{fpath}.lock")).exists());
+        drop(fs::remove_file(&fpath));
+    }
 
-                Cmd::Get(_)
-                | Cmd::Add(_)
-                | Cmd::Edit(_)
-                | Cmd::Rm(_)
-                | Cmd::Del(_)
-                | Cmd::Decr(_)
-                | Cmd::Reset => (),
-            }
+    #[test]
+    fn lock_keeps_the_lock_of_another_process() {
+        let fpath = temp_file("lock-owner");
+        let guard = lock(&fpath);
+        assert!(guard.is_ok());
+        // The lock was removed as stale, and taken by another process
+        fs::write(format!("{fpath}.lock"), "other").unwrap_or_default();
+        drop(guard);
+        assert!(Path::new(&format!("{fpath}.lock")).exists());
+        drop(fs::remove_file(format!("{fpath}.lock")));
+    }
+
+    #[test]
+    fn lock_removes_a_stale_lock() {
+        let fpath = temp_file("lock-stale");
+        fs::write(format!("{fpath}.lock"), "killed").unwrap_or_default();
+        let guard = lock(&fpath);
+        assert!(guard.is_ok());
+        drop(guard);
+        assert!(!Path::new(&format!("{fpath}.lock")).exists());
+    }
+
+    #[test]
+    fn split_line_on_semicolons() {
+        assert_eq!(
+            split_line("/home/user;f;folder;1"),
+            ["/home/user", "f", "folder", "1"]
+        );
+        assert_eq!(split_line("/home/user;;1"), ["/home/user", "", "1"]);
+        assert_eq!(split_line(""), [""]);
+    }
+
+    #[test]
+    fn split_line_keeps_escaped_semicolons() {
+        assert_eq!(split_line("/home/a\\;b/c;f;1"), ["/home/a\\;b/c", "f", "1"]);
+        assert_eq!(split_line("/a\\;;f;1"), ["/a\\;", "f", "1"]);
+        assert_eq!(split_line("/a\\\\;f;1"), ["/a\\\\", "f", "1"]);
+    }
+
+    #[test]
+    fn escaped_path_round_trip() {
+        for path in [
+            "/home/a;b/c",
+            "/home/a\\",
+            "/home/a\\;b",
+            "/home/;;",
+            "/home/user",
+        ] {
+            let line = format!("{};f;1", escape_path(path));
+            assert_eq!(split_line(&line).len(), 3, "{line}");
+            assert_eq!(
+                split_line(&line).first().map(|pth| unescape_path(pth)),
+                Some(String::from(path))
+            );
         }
     }
+
+    #[test]
+    fn std_path_of_absolute_paths() {
+        assert_eq!(std("/home/user"), "/home/user");
+        assert_eq!(std("/home/user/"), "/home/user");
+        assert_eq!(std("/"), "/");
+        assert_eq!(std("/Home/User/Docs"), "/Home/User/Docs");
+        assert_eq!(std("D:\\Windows\\PeRso"), "d:/Windows/PeRso");
+    }
+
+    #[test]
+    fn std_path_resolves_the_dots() {
+        assert_eq!(std("/home/./user/../other"), "/home/other");
+        assert_eq!(std("/home/user/a/b/../../c/./d/.."), "/home/user/c");
+        assert_eq!(std("/../home/.."), "/");
+        assert_eq!(std("/home/../../.."), "/");
+        assert_eq!(std("/home/..user/.x"), "/home/..user/.x");
+        assert_eq!(std("C:\\Users\\..\\..\\Windows"), "c:/Windows");
+    }
+
+    #[test]
+    fn std_path_of_drives() {
+        assert_eq!(std("C:"), "c:/");
+        assert_eq!(std("C:\\"), "c:/");
+        assert_eq!(std("C:/.."), "c:/");
+        assert_eq!(std("C:foo"), "c:/foo");
+        assert_eq!(std("c:foo\\..\\bar"), "c:/bar");
+        assert_eq!(std("D:/Users/Me"), "d:/Users/Me");
+    }
+
+    #[test]
+    fn std_path_of_network_paths() {
+        assert!(is_unc("\\\\server\\share"));
+        assert!(is_unc("//server/share/dir"));
+        assert!(!is_unc("//home"));
+        assert!(!is_unc("///home/user"));
+        assert!(!is_unc("/home/user"));
+        assert_eq!(std("\\\\server\\share\\dir\\"), "//server/share/dir");
+        assert_eq!(std("//server//share/dir"), "//server/share/dir");
+        assert_eq!(std("\\\\nas\\media\\movies"), "//nas/media/movies");
+        assert_eq!(std("//home"), "/home");
+        assert_eq!(std("///home/user"), "/home/user");
+    }
+
+    #[test]
+    fn std_path_removes_the_trailing_separators() {
+        assert_eq!(std("/home/user///"), "/home/user");
+        assert_eq!(std("/home//user"), "/home/user");
+        assert_eq!(std("C:\\Users\\"), "c:/Users");
+        assert_eq!(std("//"), "/");
+    }
+
+    #[test]
+    fn std_path_of_relative_paths() {
+        let here = current_dir().unwrap_or_default();
+        assert_eq!(std("."), here);
+        assert_eq!(std("folder"), format!("{here}/folder"));
+    }
+
+    #[test]
+    fn std_path_without_current_dir() {
+        assert_eq!(std_path_in("folder", || None), Err(NoCurrentDir));
+        assert_eq!(std_path_in("./folder", || None), Err(NoCurrentDir));
+        assert_eq!(
+            std_path_in("folder", || Some(String::from("/home"))).ok(),
+            Some(String::from("/home/folder"))
+        );
+        assert_eq!(
+            std_path_in("/home/user", || None).ok(),
+            Some(String::from("/home/user"))
+        );
+    }
+
+    #[test]
+    fn cmd_from_values() {
+        assert_eq!(
+            cmd("-add", &["f", "/home/user"]).ok(),
+            Some(String::from("<add f /home/user>"))
+        );
+        assert_eq!(
+            cmd("-add", &["--after", "g", "f"]).ok(),
+            Some(String::from("<add f --after g>"))
+        );
+        assert_eq!(
+            cmd("-prune", &["--dry-run"]).ok(),
+            Some(String::from("<prune --dry-run>"))
+        );
+        assert_eq!(
+            cmd("-set", &["f", "5"]).ok(),
+            Some(String::from("<set f 5>"))
+        );
+        assert_eq!(
+            cmd("-move", &["/a", "/b"]).ok(),
+            Some(String::from("<move /a /b>"))
+        );
+        assert_eq!(
+            cmd("-get", &["f", "sub/dir"]).ok(),
+            Some(String::from("<goto f sub/dir>"))
+        );
+    }
+
+    #[test]
+    fn decr_by_position() {
+        let decr = |values: &[&str]| {
+            let mut command = "-decrement".to_cmd();
+            for value in values {
+                assert_eq!(command.append(String::from(*value)), Ok(()));
+            }
+            assert_eq!(Some(&mut command).append_default(None), Ok(()));
+            command
+        };
+        assert!(matches!(decr(&["30"]), Cmd::Decr(None, Some(30))));
+        assert!(matches!(decr(&["0", "5"]), Cmd::Decr(Some(short), Some(5)) if short == "0"));
+        assert!(matches!(decr(&["myshort"]), Cmd::Decr(Some(short), None) if short == "myshort"));
+        assert!(matches!(decr(&[]), Cmd::Decr(None, None)));
+    }
+
+    #[test]
+    fn cmd_without_current_dir() {
+        let mut add = "-add".to_cmd();
+        assert_eq!(Some(&mut add).append_default(None), Err(NoCurrentDir));
+        let mut edit = "-edit".to_cmd();
+        assert_eq!(edit.append(String::from("f")), Ok(()));
+        assert_eq!(Some(&mut edit).append_default(None), Err(NoCurrentDir));
+        let mut get = "-get".to_cmd();
+        assert_eq!(Some(&mut get).append_default(None), Ok(()));
+    }
 }
@@ -1,77 +1,392 @@
-use core::fmt::Write;
-use std::{fs, path, process};
+use core::cmp::Reverse;
+use core::fmt::Write as _;
+use std::io::{self, BufRead, IsTerminal as _};
+use std::{collections, env, fs, path, process, time};
 
-use crate::commands::{Cmd, ShortPath};
-use crate::errors::{ReadError, SingleError, WriteError};
-use crate::{general_error, user_error};
+use crate::commands::{
+    escape_path, lock, path2dir, split_line, std_path, unescape_path, write_atomic, Cmd, Scope,
+    ShortPair, ShortPath, ShortsPath, AGE_FACTOR,
+};
+use crate::errors::{InteractionError as _, ReadError as _, SingleError as _, WriteError as _};
+use crate::hist;
+use crate::json::{self, Json};
+use crate::{command_error, data_error, file_error, general_error, user_error, GlobalData};
 
-/// Trait to read a vector of a line of the directory file
-/// Enables to get path, shortuts, priority from a dline
-trait ReadVec {
-    /// Get the path of the directory
-    fn join_elts(&self, deb: usize, offset: usize, msgf: &str) -> String;
+/// Marker added at the end of a line of the directory file, when its path doesn't exist anymore.
+const BROKEN: &str = "!broken";
+
+/// Mark printed after the broken directories by `-state`, `-stats --unused` and `-search` (a cross).
+const BROKEN_MARK: &str = " \u{2717}";
+
+/// Marker added at the end of a line of the directory file, when its priority isn't incremented on use.
+const FROZEN: &str = "!frozen";
+
+/// Prefix of the marker added at the end of a line of the directory file, with the time of its last use (in seconds since the Unix Epoch).
+const ACCESS: &str = "@";
+
+/// Time after which the priority of a directory counts for half as much (one week, in seconds).
+const HALF_LIFE: f64 = 604_800.0;
+
+/// Minimum factor applied to the priority of a directory, so that the old directories are still sorted by usage.
+const MIN_DECAY: f64 = 0.0625;
+
+/// Characters that can't be in a shortcut, as they would corrupt the directory file (`\` escapes the next `;`, see `split_line`), or be read as a path.
+const FORBIDDEN: [char; 5] = [';', '\n', '\r', '/', '\\'];
+
+/// Version of the format written by `-export-json`, to be incremented every time its fields change.
+const JSON_VERSION: u32 = 2;
+
+/// Fields of the JSON of `-export-json` that were renamed, with the version that renamed them and their old name (see `json_field`).
+const JSON_RENAMES: [(u32, &str, &str); 1] = [(2, "shortcuts", "names")];
+
+/// Strategy used to find the directory of a shortcut (see `GlobalData`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// The name of the directory is the given shortcut.
+    Basename,
+    /// A shortcut of the directory is the given shortcut (the usage of the directory is incremented).
+    Exact,
+    /// A shortcut of the directory is close to the given shortcut (see `fuzzy_score`).
+    Fuzzy,
+    /// A shortcut of the directory starts with the given shortcut (the one with the highest frecency wins, see `frecency`).
+    Prefix,
 }
 
-impl ReadVec for Vec<&str> {
-    fn join_elts(&self, deb: usize, offset: usize, msg: &str) -> String {
-        let x = self
-            .get(deb..self.len().checked_sub(offset).data_error(msg, None))
-            .data_error(msg, None);
-        x.join(";")
+impl Tier {
+    /// Function to get the name of the tier, as used in `GOTO_MATCH`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Prefix => "prefix",
+            Self::Fuzzy => "fuzzy",
+            Self::Basename => "basename",
+        }
+    }
+
+    /// Function to parse a comma-separated list of tiers.
+    /// # Arguments
+    /// * `spec` - The list of tiers, e.g. `exact,prefix,fuzzy`
+    /// # Returns
+    /// The valid tiers, in the given order (the invalid ones are ignored with an error).
+    pub fn parse_list(spec: &str) -> Vec<Self> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| match name {
+                "exact" => Some(Self::Exact),
+                "prefix" => Some(Self::Prefix),
+                "fuzzy" => Some(Self::Fuzzy),
+                "basename" => Some(Self::Basename),
+                _ => {
+                    user_error!(
+                        "Invalid match mode <{name}>: expected exact, prefix, fuzzy or basename"
+                    );
+                    None
+                }
+            })
+            .collect()
     }
 }
 
-/// Structure to contain the state of the search
+/// Structure to contain the state of the search.
 #[derive(Default, Debug)]
 struct SearchState {
+    /// If a path was not found for the given shortut, `basename` contains the first path whose name is the shortcut.
+    basename: Option<String>,
+    /// If a path was not found for the given shortut, `case_insensitive` contains the first path with the shortcut in another case.
+    case_insensitive: Option<String>,
     /// If a path was found for the given shortut, `correct` contains the path.
     correct: Option<String>,
-    /// If a path was not found for the given shortut, `prioritised` contains the path with the highest priority.
+    /// The given shortcuts that already exist with the same case (they aren't added again, nor removed in another case).
+    existing: Vec<String>,
+    /// If a path was not found for the given shortut, `fuzzy` contains the path with the closest shortcut.
+    fuzzy: Option<String>,
+    /// The score of `fuzzy` (the lower, the better).
+    fuzzy_score: f64,
+    /// The highest frecency found (see `frecency`).
+    max_frecency: f64,
+    /// With `-move`, the shortcuts and the priority of the moved directory, if its new path already has a line (see `check_move`).
+    merged: Option<(Vec<String>, u32)>,
+    /// The time of the search, in seconds since the Unix Epoch.
+    now: u64,
+    /// If a path was not found for the given shortut, `prefix` contains the path with the highest frecency with a shortcut starting with it.
+    prefix: Option<String>,
+    /// The frecency of `prefix`.
+    prefix_frecency: f64,
+    /// If `interactive` is set (see `GlobalData`), the paths with a shortcut starting with the given shortut, with their shortcuts.
+    prefixes: Vec<(String, String)>,
+    /// If a path was not found for the given shortut, `prioritised` contains the path with the highest frecency (see `frecency`).
+    /// It is only navigated to when no shortcut is given: an unknown shortcut is an error (see `read`).
     prioritised: Option<String>,
-    /// The highest priority found.
-    max_priory: u32,
+    /// The paths removed by `-prune` (or that would be removed with `--dry-run`).
+    pruned: Vec<String>,
+    /// The shortcuts already removed, when removing several shortcuts.
+    removed: Vec<String>,
+}
+
+/// Structure to contain the markers at the end of a line in the directory file.
+#[derive(Debug, Default, Clone, Copy)]
+struct Marks {
+    /// `true` if the path was found not to exist when the shortcut was last used.
+    broken: bool,
+    /// `true` if the priority isn't incremented when the shortcut is used.
+    frozen: bool,
+    /// The time the shortcut was last used, in seconds since the Unix Epoch (0 if unknown).
+    last_access: u64,
 }
 
-/// Structure to contain the data of a line in the directory file
+impl Marks {
+    /// Function to get the end of a line of the directory file, containing the markers if needed.
+    fn end(self) -> String {
+        let access = if self.last_access == 0 {
+            String::new()
+        } else {
+            format!(";{ACCESS}{}", self.last_access)
+        };
+        let flags = match (self.frozen, self.broken) {
+            (false, false) => "",
+            (false, true) => ";!broken",
+            (true, false) => ";!frozen",
+            (true, true) => ";!frozen;!broken",
+        };
+        format!("{access}{flags}")
+    }
+}
+
+/// Structure to contain the data of a line in the directory file.
 #[derive(Debug)]
 struct DirsLine<'dirline> {
-    /// The path of the directory
+    /// The markers of the line.
+    marks: Marks,
+    /// The path of the directory.
     path: &'dirline str,
-    /// The shortuts of the directory
-    shorts: &'dirline [&'dirline str],
-    /// The priority of the directory
+    /// The priority of the directory.
     priory: u32,
-    /// The priority of the directory incremented by `incr` (see `GlobalData`)
+    /// The priority of the directory incremented by `incr` (see `GlobalData`).
     priory2: u32,
+    /// The shortuts of the directory.
+    shorts: &'dirline [&'dirline str],
 }
 
-impl<'dirline> DirsLine<'dirline> {
-    /// Function to convert a `DirsLine` to a string
+impl DirsLine<'_> {
+    /// Function to convert a `DirsLine` to a string.
     fn join(&self, sep: &str) -> String {
         format!(
-            "{}{}{}{}{}",
+            "{}{}{}{}{}{}",
             self.path,
             sep,
             self.shorts.join(sep),
             sep,
-            self.priory
+            self.priory,
+            self.marker()
         )
     }
+
+    /// Function to get the end of the line, containing the markers if needed.
+    fn marker(&self) -> String {
+        self.marks.end()
+    }
+}
+
+/// Function to remove the markers from a split line of the directory file.
+/// # Arguments
+/// * `vecline` - The line of the directory file, split on `;`
+/// # Returns
+/// The markers found at the end of the line.
+fn pop_markers(vecline: &mut Vec<&str>) -> Marks {
+    let mut marks = Marks::default();
+    loop {
+        match vecline.last() {
+            Some(&BROKEN) => marks.broken = true,
+            Some(&FROZEN) => marks.frozen = true,
+            Some(last) => match last
+                .strip_prefix(ACCESS)
+                .and_then(|secs| secs.parse::<u64>().ok())
+            {
+                Some(secs) => marks.last_access = secs,
+                None => return marks,
+            },
+            None => return marks,
+        }
+        vecline.pop();
+    }
+}
+
+/// Function to split a line of the directory file into its path, its shortcuts and its priority.
+/// # Arguments
+/// * `dline` - The line of the directory file
+/// # Returns
+/// The path, the shortcuts and the priority of the line, or `None` if the line is empty or malformed.
+/// # Example
+/// ```ignore
+/// let (path, shorts, priory) = split_dline("/home/user/folder;f;folder;1").unwrap();
+/// assert!(shorts == vec!["f", "folder"]);
+/// ```
+fn split_dline(dline: &str) -> Option<(&str, Vec<&str>, u32)> {
+    let mut vecline: Vec<&str> = split_line(dline.trim());
+    pop_markers(&mut vecline);
+    match vecline.as_slice() {
+        [path, shorts @ .., priory] if !shorts.is_empty() => priory
+            .parse::<u32>()
+            .ok()
+            .map(|priory_int| (*path, shorts.to_vec(), priory_int)),
+        _ => None,
+    }
+}
+
+/// Function to check that a line of the directory file is valid.
+/// # Arguments
+/// * `dline` - The line of the directory file
+/// # Returns
+/// `Err` with the reason if the line is invalid (empty lines are valid).
+/// # Example
+/// ```ignore
+/// assert!(check_dline("/home/user/folder;f;folder;1").is_ok());
+/// assert!(check_dline("/home/user/folder;1").is_err());
+/// ```
+fn check_dline(dline: &str) -> Result<(), String> {
+    let mut vecline: Vec<&str> = split_line(dline.trim());
+    pop_markers(&mut vecline);
+    match vecline.as_slice() {
+        [""] => Ok(()),
+        [] | [_] | [_, _] => Err(String::from("missing path, shortcut or priority")),
+        ["", ..] => Err(String::from("empty path")),
+        [_, shorts @ .., priory] => {
+            if shorts.iter().any(|sh| sh.is_empty()) {
+                Err(String::from("empty shortcut"))
+            } else {
+                priory
+                    .parse::<u32>()
+                    .map(|_| ())
+                    .map_err(|er| format!("priority <{priory}> is not an integer ({er})"))
+            }
+        }
+    }
+}
+
+/// Function to compute the Levenshtein distance between two strings.
+/// # Arguments
+/// * `fst` - The first string
+/// * `snd` - The second string
+/// # Returns
+/// The minimum number of insertions, deletions and substitutions to go from `fst` to `snd`.
+/// # Example
+/// ```ignore
+/// assert!(levenshtein("docs", "dogs") == 1);
+/// ```
+fn levenshtein(fst: &str, snd: &str) -> usize {
+    let snd_chars: Vec<char> = snd.chars().collect();
+    let mut prev: Vec<usize> = (0..=snd_chars.len()).collect();
+    for (idx, fst_char) in fst.chars().enumerate() {
+        let mut curr = vec![idx.saturating_add(1)];
+        for (jdx, snd_char) in snd_chars.iter().enumerate() {
+            let subst = prev
+                .get(jdx)
+                .copied()
+                .unwrap_or_default()
+                .saturating_add(usize::from(fst_char != *snd_char));
+            let del = prev
+                .get(jdx.saturating_add(1))
+                .copied()
+                .unwrap_or_default()
+                .saturating_add(1);
+            let ins = curr.last().copied().unwrap_or_default().saturating_add(1);
+            curr.push(subst.min(del).min(ins));
+        }
+        prev = curr;
+    }
+    prev.last().copied().unwrap_or_default()
+}
+
+/// Function to check if the characters of a string appear in another one, in the same order.
+/// # Arguments
+/// * `sub` - The string whose characters are searched
+/// * `full` - The string to search in
+/// # Returns
+/// `true` if `sub` is a subsequence of `full`.
+/// # Example
+/// ```ignore
+/// assert!(is_subsequence("docs", "documents"));
+/// assert!(!is_subsequence("sdoc", "documents"));
+/// ```
+fn is_subsequence(sub: &str, full: &str) -> bool {
+    let mut chars = full.chars();
+    sub.chars()
+        .all(|sub_char| chars.any(|full_char| full_char == sub_char))
+}
+
+/// Function to compute the fuzzy score of a line of the directory file.
+/// # Arguments
+/// * `dirline` - The line of the directory file
+/// * `short` - The shortut to search for
+/// * `gdata` - The static data of the program (for `fuzzy_threshold` and `fuzzy_weight`)
+/// # Returns
+/// The score of the line (the lower, the better), or `None` if no shortcut of the line is close enough to `short`.
+/// # Note
+/// A shortcut is close enough if `short` is a subsequence of it (e.g. `docs` for `documents`), or if their Levenshtein distance is at most `fuzzy_threshold`.
+/// The score is the Levenshtein distance between `short` and the closest shortcut of the line, minus `fuzzy_weight * ln(1 + priority)`.
+/// This way, when several shortcuts are as close to `short`, the most used one wins.
+#[expect(
+    clippy::float_arithmetic,
+    reason = "the priority is weighted against the distance"
+)]
+fn fuzzy_score(dirline: &DirsLine, short: &str, gdata: &GlobalData) -> Option<f64> {
+    dirline
+        .shorts
+        .iter()
+        .map(|sh| (levenshtein(short, sh), is_subsequence(short, sh)))
+        .filter(|&(dist, subseq)| subseq || dist <= gdata.fuzzy_threshold)
+        .map(|(dist, _)| dist)
+        .min()
+        .map(|dist| {
+            #[expect(
+                clippy::cast_precision_loss,
+                clippy::as_conversions,
+                reason = "a Levenshtein distance is far below 2^52"
+            )]
+            let distance = dist as f64;
+            (-gdata.fuzzy_weight).mul_add(f64::from(dirline.priory).ln_1p(), distance)
+        })
+}
+
+/// Function to compute the frecency of a line of the directory file.
+/// # Arguments
+/// * `dirline` - The line of the directory file
+/// * `now` - The current time, in seconds since the Unix Epoch
+/// # Returns
+/// The priority of the line, halved every `HALF_LIFE` since its last use (but at least multiplied by `MIN_DECAY`).
+/// # Note
+/// The lines that were never used with a timestamp (e.g. written by an older version) are as old as possible.
+#[expect(
+    clippy::float_arithmetic,
+    reason = "the priority decays continuously with the age"
+)]
+fn frecency(dirline: &DirsLine, now: u64) -> f64 {
+    #[expect(
+        clippy::cast_precision_loss,
+        clippy::as_conversions,
+        reason = "an age in seconds is far below 2^52"
+    )]
+    let age = now.saturating_sub(dirline.marks.last_access) as f64;
+    let half: f64 = 0.5;
+    f64::from(dirline.priory) * half.powf(age / HALF_LIFE).max(MIN_DECAY)
 }
 
-/// Function to get the path of a directory from the directory file
+/// Function to get the path of a directory from the directory file.
 /// # Arguments
 /// * `dirline` - The line of the directory file
 /// * `success` - A mutable reference to a boolean to indicate if the path was found
 /// * `sstate` - A mutable reference to the state of the search
 /// * `short` - The shortut to search for
+/// * `gdata` - The static data of the program
 /// # Returns
-/// The line of the directory file
+/// The line of the directory file.
 /// # Note
 /// If the path was found, `success` is set to `true` and `sstate.correct` contains the path.
-/// If the path was not found, `sstate.prioritised` contains the path with the highest priority.
+/// The line is then marked as broken if the path doesn't exist anymore, and its priority is incremented unless it is frozen.
+/// If the path was not found, `sstate.prioritised` contains the path with the highest priority, and the other fields the candidates of the enabled tiers (see `Tier`).
 /// # Example
-/// ```
+/// ```ignore
 /// let mut success = false;
 /// let mut sstate = SearchState::default();
 /// let dirline = DirsLine {
@@ -79,11 +394,12 @@ impl<'dirline> DirsLine<'dirline> {
 ///    shorts: &["f", "folder"],
 ///    priory: 1,
 ///    priory2: 2,
+///    marks: Marks::default(),
 /// };
-/// let path = get(&dirline, &mut success, &mut sstate, "f");
+/// let path = get(&dirline, &mut success, &mut sstate, "f", &GlobalData::default());
 /// ```
 /// # Panics
-/// If the shortut is not found in the line
+/// If the shortut is not found in the line.
 /// # Note
 /// The directory file is a simple text file with the following format:
 /// ```text
@@ -92,132 +408,327 @@ impl<'dirline> DirsLine<'dirline> {
 /// ```
 /// Where the first field is the path of the directory, the second field is the shortuts of the directory, the third field is the priority of the directory.
 /// The last line is the most recent directory pushed.
-fn get(dirline: &DirsLine, success: &mut bool, sstate: &mut SearchState, short: &str) -> String {
-    if dirline.shorts.contains(&short) {
+fn get(
+    dirline: &DirsLine,
+    success: &mut bool,
+    sstate: &mut SearchState,
+    short: &str,
+    gdata: &GlobalData,
+) -> String {
+    if gdata.tiers.contains(&Tier::Exact) && dirline.shorts.contains(&short) {
         sstate.correct = Some(String::from(dirline.path));
         *success = true;
+        let marks = Marks {
+            broken: !path::Path::new(&unescape_path(dirline.path)).exists(),
+            last_access: sstate.now,
+            ..dirline.marks
+        };
         format!(
-            "{};{};{}",
+            "{};{};{}{}",
             dirline.path,
             dirline.shorts.join(";"),
-            dirline.priory2
+            if marks.frozen {
+                dirline.priory
+            } else {
+                dirline.priory2
+            },
+            marks.end()
         )
     } else {
-        if dirline.priory > sstate.max_priory {
-            sstate.max_priory = dirline.priory;
+        if gdata.tiers.contains(&Tier::Exact)
+            && !gdata.case_sensitive
+            && sstate.case_insensitive.is_none()
+            && !short.is_empty()
+            && dirline
+                .shorts
+                .iter()
+                .any(|sh| sh.eq_ignore_ascii_case(short))
+        {
+            sstate.case_insensitive = Some(String::from(dirline.path));
+        }
+        let frec = frecency(dirline, sstate.now);
+        if frec > sstate.max_frecency {
+            sstate.max_frecency = frec;
             sstate.prioritised = Some(String::from(dirline.path));
         }
+        if !short.is_empty() {
+            if gdata.interactive && dirline.shorts.iter().any(|sh| sh.starts_with(short)) {
+                sstate
+                    .prefixes
+                    .push((String::from(dirline.path), dirline.shorts.join(" ")));
+            }
+            if gdata.tiers.contains(&Tier::Prefix)
+                && dirline.shorts.iter().any(|sh| sh.starts_with(short))
+                && (sstate.prefix.is_none() || frec > sstate.prefix_frecency)
+            {
+                sstate.prefix_frecency = frec;
+                sstate.prefix = Some(String::from(dirline.path));
+            }
+            if gdata.tiers.contains(&Tier::Fuzzy) {
+                if let Some(score) = fuzzy_score(dirline, short, gdata) {
+                    if sstate.fuzzy.is_none() || score < sstate.fuzzy_score {
+                        sstate.fuzzy_score = score;
+                        sstate.fuzzy = Some(String::from(dirline.path));
+                    }
+                }
+            }
+            if gdata.tiers.contains(&Tier::Basename)
+                && sstate.basename.is_none()
+                && dirline.path.rsplit('/').next() == Some(short)
+            {
+                sstate.basename = Some(String::from(dirline.path));
+            }
+        }
         dirline.join(";")
     }
 }
 
-/// Function to remove a shortut from a line of the directory file
+/// Function to remove shortuts from a line of the directory file.
 /// # Arguments
 /// * `dirline` - The line of the directory file
-/// * `success` - A mutable reference to a boolean to indicate if the shortut was found
-/// * `short` - The shortut to remove
+/// * `success` - A mutable reference to a boolean to indicate if all the shortuts were found
+/// * `sstate` - A mutable reference to the state of the search
+/// * `shorts` - The shortuts to remove
+/// * `case_sensitive` - `false` to also remove the shortcuts in another case (see `GlobalData`)
 /// # Returns
-/// The line of the directory file
+/// The line of the directory file.
 /// # Example
-/// ```
+/// ```ignore
 /// let mut success = false;
+/// let mut sstate = SearchState::default();
 /// let dirline = DirsLine {
 ///   path: "/home/user/folder",
 ///  shorts: &["f", "folder"],
 /// priory: 1,
 /// priory2: 2,
+/// marks: Marks::default(),
 /// };
-/// let line = remove(&dirline, &mut success, "f");
+/// let line = remove(&dirline, &mut success, &mut sstate, &[String::from("F")], false);
 /// ```
 /// # Panics
-/// If the shortut is not found in the line
+/// If the shortut is not found in the line.
 /// # Note
-///
-fn remove(dirline: &DirsLine, success: &mut bool, short: &str) -> String {
-    if dirline.shorts.contains(&short) {
+/// The removed shortcuts are added to `sstate.removed`, and the line is removed if it has no shortcuts left.
+/// A shortcut is only removed in another case if it doesn't exist with the same case in the file (see `sstate.existing`).
+fn remove(
+    dirline: &DirsLine,
+    success: &mut bool,
+    sstate: &mut SearchState,
+    shorts: &[String],
+    case_sensitive: bool,
+) -> String {
+    let matches = |rm: &String, sh: &str| {
+        rm == sh
+            || (!case_sensitive && !sstate.existing.contains(rm) && rm.eq_ignore_ascii_case(sh))
+    };
+    let kept: Vec<&str> = dirline
+        .shorts
+        .iter()
+        .filter(|sh| !shorts.iter().any(|rm| matches(rm, sh)))
+        .copied()
+        .collect();
+    if kept.len() == dirline.shorts.len() {
+        return dirline.join(";");
+    }
+    let removed: Vec<String> = shorts
+        .iter()
+        .filter(|rm| dirline.shorts.iter().any(|sh| matches(rm, sh)))
+        .filter(|rm| !sstate.removed.contains(rm))
+        .cloned()
+        .collect();
+    sstate.removed.extend(removed);
+    if sstate.removed.len() >= shorts.len() {
         *success = true;
-        if dirline.shorts.len() == 1 {
-            return String::new();
-        }
+    }
+    if kept.is_empty() {
+        String::new()
+    } else {
         format!(
-            "{};{};{}",
+            "{};{};{}{}",
             dirline.path,
-            dirline
-                .shorts
-                .iter()
-                .filter(|&&sh| sh != short)
-                .copied()
-                .collect::<Vec<_>>()
-                .join(";"),
-            dirline.priory
+            kept.join(";"),
+            dirline.priory,
+            dirline.marker()
         )
-    } else {
-        dirline.join(";")
     }
 }
 
-/// Function to add a shortut to a line of the directory file
+/// Function to select shortcuts in the directory file, from the user input.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// The selected shortcuts.
+/// # Note
+/// The shortcuts are printed on stderr with a number, and the user enters the numbers of the shortcuts to select, separated by spaces.
+/// Nothing is selected if stdin is not a terminal.
+pub fn select_shorts(dpath: &str) -> Vec<String> {
+    if !io::stdin().is_terminal() {
+        user_error!("Selecting shortcuts requires a terminal");
+        return vec![];
+    }
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let shorts = binding
+        .lines()
+        .filter_map(split_dline)
+        .flat_map(|(pth, line_shorts, _)| line_shorts.into_iter().map(move |sh| (sh, pth)))
+        .collect::<Vec<_>>();
+    #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+    {
+        for (idx, (short, pth)) in shorts.iter().enumerate() {
+            eprintln!(
+                "{:>3}. {short} ({})",
+                idx.saturating_add(1),
+                unescape_path(pth)
+            );
+        }
+        eprint!("Shortcuts to remove (numbers separated by spaces): ");
+    };
+    let mut input = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut input)
+        .read_error("stdin", None);
+    let mut selected: Vec<String> = vec![];
+    for word in input.split_whitespace() {
+        match word
+            .parse::<usize>()
+            .ok()
+            .and_then(|nb| nb.checked_sub(1))
+            .and_then(|idx| shorts.get(idx))
+        {
+            Some((short, _)) if !selected.iter().any(|sh| sh == short) => {
+                selected.push(String::from(*short));
+            }
+            Some(_) => (),
+            None => user_error!("Invalid shortcut number <{word}>"),
+        }
+    }
+    selected
+}
+
+/// Function to let the user choose between the directories with a shortcut starting with the given one.
+/// # Arguments
+/// * `candidates` - The paths of the directories, with their shortcuts (see `sstate.prefixes`)
+/// # Returns
+/// The selected path, or `None` if there are less than 2 candidates, if stdin is not a terminal, or if the selection is invalid.
+/// # Note
+/// The directories are printed on stderr with a number, and the user enters the number of the directory to go to.
+/// When `None` is returned, the directory is found as without `interactive` (see `GlobalData`).
+/// It is called once the directory file is written and unlocked by `read`, so that another process doesn't wait for the answer.
+fn choose_prefix(candidates: &[(String, String)]) -> Option<String> {
+    if candidates.len() < 2 || !io::stdin().is_terminal() {
+        return None;
+    }
+    #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+    {
+        for (idx, (pth, shorts)) in candidates.iter().enumerate() {
+            eprintln!(
+                "{:>3}. {shorts} ({})",
+                idx.saturating_add(1),
+                unescape_path(pth)
+            );
+        }
+        eprint!("Directory to go to (number): ");
+    };
+    let mut input = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut input)
+        .read_error("stdin", None);
+    let choice = input
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|nb| nb.checked_sub(1))
+        .and_then(|idx| candidates.get(idx))
+        .map(|(pth, _)| pth.clone());
+    if choice.is_none() {
+        user_error!("Invalid directory number <{}>", input.trim());
+    }
+    choice
+}
+
+/// Function to add shortuts to a line of the directory file.
 /// # Arguments
 /// * `dirline` - The line of the directory file
-/// * `success` - A mutable reference to a boolean to indicate if the shortut was added
-/// * `new_short` - The new shortut to add
+/// * `success` - A mutable reference to a boolean to indicate if the shortuts were added
+/// * `sstate` - The state of the search
+/// * `new_shorts` - The new shortuts to add
 /// * `path` - The path of the directory
 /// # Returns
-/// The line of the directory file
+/// The line of the directory file.
 /// # Example
-/// ```
+/// ```ignore
 /// let mut success = false;
 /// let dirline = DirsLine {
 ///   path: "/home/user/folder",
 ///   shorts: &["f", "folder"],
 ///   priory: 1,
 ///   priory2: 2,
+///   marks: Marks::default(),
 /// };
-/// let line = add(&dirline, &mut success, "f2", "/home/user/folder");
+/// let line = add(&dirline, &mut success, &SearchState::default(), &[String::from("f2")], "/home/user/folder");
 /// ```
-/// # Panics
-/// If the shortut already exists
-fn add(dirline: &DirsLine, success: &mut bool, new_short: &str, path: &str) -> String {
-    if dirline.shorts.contains(&new_short) {
-        user_error!("Shortcut {new_short} already exists");
-        *success = true;
-        dirline.join(";")
-    } else if path == dirline.path {
+/// # Note
+/// The shortcuts that already exist (see `sstate.existing`) are not added.
+/// If the line has the same path, the shortcuts are merged into it and `success` is set, so that `read` doesn't append a duplicate line for the path.
+fn add(
+    dirline: &DirsLine,
+    success: &mut bool,
+    sstate: &SearchState,
+    new_shorts: &[String],
+    path: &str,
+) -> String {
+    if path == dirline.path {
         *success = true;
-        if dirline.shorts.contains(&new_short) {
-            user_error!("Shortcut {new_short} already exists");
-            dirline.join(";")
-        } else {
-            format!(
-                "{path};{};{new_short};{}",
-                dirline.shorts.join(";"),
-                dirline.priory2
-            )
-        }
+        format!(
+            "{path};{};{};{}{}",
+            dirline.shorts.join(";"),
+            new_only(new_shorts, &sstate.existing),
+            dirline.priory2,
+            dirline.marker()
+        )
     } else {
         dirline.join(";")
     }
 }
 
+/// Function to join the new shortcuts that don't already exist.
+/// # Arguments
+/// * `new_shorts` - The shortcuts to add
+/// * `existing` - The shortcuts that already exist
+/// # Returns
+/// The shortcuts to add, separated by `;`, once each even if they were given several times.
+fn new_only(new_shorts: &[String], existing: &[String]) -> String {
+    let mut kept: Vec<&str> = vec![];
+    for short in new_shorts {
+        if !existing.contains(short) && !kept.contains(&short.as_str()) {
+            kept.push(short);
+        }
+    }
+    kept.join(";")
+}
+
 /// # Arguments
 /// * `dirline` - The line of the directory file
 /// * `success` - A mutable reference to a boolean to indicate if the shortut was edited
 /// * `short` - The new shortut
 /// * `path` - The path of the directory
 /// # Returns
-/// The line of the directory file
+/// The line of the directory file.
 /// # Example
-/// ```
+/// ```ignore
 /// let mut success = false;
 /// let dirline = DirsLine {
 ///   path: "/home/user/folder",
 ///   shorts: &["f", "folder"],
 ///   priory: 1,  
 ///   priory2: 2,
+///   marks: Marks::default(),
 /// };
 /// ```
 /// # Panics
-/// If the path already exists
+/// If the path already exists.
 ///
 fn edit(dirline: &DirsLine, success: &mut bool, short: &str, path: &str) -> String {
     if dirline.path == path {
@@ -226,289 +737,2780 @@ fn edit(dirline: &DirsLine, success: &mut bool, short: &str, path: &str) -> Stri
         dirline.join(";")
     } else if dirline.shorts.contains(&short) {
         *success = true;
-        format!("{path};{};{}", dirline.shorts.join(";"), dirline.priory)
+        // Only the path changes: the shortcuts, the priority and the markers are kept
+        DirsLine { path, ..*dirline }.join(";")
     } else {
         dirline.join(";")
     }
 }
 
-///////////////////////////////: command keywords functions  :///////////////////////////////
-
-/// Function to read a line of the directory file
+/// Function to find the path of a shortcut to clone.
 /// # Arguments
-/// * `rdline` - The line of the directory file
-/// * `args` - The arguments of the command
-/// * `success` - A mutable reference to a boolean to indicate if the command was successful
-/// * `incr` - The increment value
+/// * `dirline` - The line of the directory file
+/// * `success` - A mutable reference to a boolean to indicate if the new shortcut already exists
 /// * `sstate` - A mutable reference to the state of the search
+/// * `old` - The shortcut to clone
+/// * `new` - The new shortcut
 /// # Returns
-/// The line of the directory file
-/// # Example
-/// ```
-/// let mut success = false;
-/// let mut sstate = SearchState::default();
-/// let rdline = "/home/user/folder;f;folder;1";
-/// let args = vec!["get".to_string(), "f".to_string()];
-/// let line = read_dline(&rdline, &args, &mut success, 1, &mut sstate);
-/// ```
-/// # Panics
-/// If the command is invalid
-fn read_dline(
-    rdline: &str,
-    args: &[Cmd],
+/// The line of the directory file, unchanged.
+/// # Note
+/// If the shortcut to clone is found, `sstate.correct` contains its path.
+/// The new line is added at the end of the file, by `read`.
+fn clone_short(
+    dirline: &DirsLine,
     success: &mut bool,
-    incr: u32,
     sstate: &mut SearchState,
+    old: &str,
+    new: &str,
 ) -> String {
-    if *success {
-        if rdline.trim().is_empty() {
-            return String::new();
+    match (dirline.shorts.contains(&new), dirline.shorts.contains(&old)) {
+        (true, _) => {
+            user_error!("Shortcut {new} already exists");
+            *success = true;
         }
-        return format!("{rdline}\n");
+        (false, true) => sstate.correct = Some(String::from(dirline.path)),
+        (false, false) => (),
     }
-    let vecline: Vec<&str> = rdline.split(';').collect();
-    if vecline.len() < 2 {
-        assert!(
-            vecline.first().unwrap_or(&"").is_empty(),
-            "Invalid rdline {rdline} found in directory library"
-        );
-        String::new()
-    } else {
-        #[allow(clippy::expect_used)]
-        let priory = vecline
-            .last()
-            .expect("[Data Error] Missing priority in dline.")
-            .parse::<u32>()
-            .data_error(format!("Priority not an integer in {rdline}"), None);
-
-        let dirline = DirsLine {
-            path: if let Some(pth) = vecline.first() {
-                pth
-            } else {
-                return String::new();
-            },
-            shorts: vecline
-                .get(
-                    1..vecline
-                        .len()
-                        .checked_sub(1)
-                        .data_error("Unable to subtract to priority", None),
-                )
-                .data_error("Missing values in line", None),
-            priory,
-            priory2: priory
-                .checked_add(incr)
-                .internal_error("Overflow on priority", None),
-        };
-
-        let line2 = if let Some(first) = args.first() {
-            match first {
-                Cmd::Get(ShortPath { short: None, .. }) => get(&dirline, success, sstate, ""),
-                Cmd::Get(ShortPath {
-                    short: Some(short), ..
-                }) => get(&dirline, success, sstate, short),
-                Cmd::Reset => format!(
-                    "{};{}",
-                    vecline.join_elts(0, 1, "Missing values in line"),
-                    0
-                ),
-                Cmd::Decr(decr) => format!(
-                    "{};{}",
-                    vecline.join_elts(0, 1, "Missing values in line"),
-                    priory.saturating_sub(*decr)
-                ),
-
-                Cmd::Rm(short) => remove(&dirline, success, short),
-                Cmd::Del(path) if *dirline.path == *path => {
-                    *success = true;
-                    return String::new();
-                }
-                Cmd::Del(_) => dirline.join(";"),
-
-                Cmd::Add(ShortPath { short: None, .. } | ShortPath { path: None, .. })
-                | Cmd::Edit(ShortPath { short: None, .. } | ShortPath { path: None, .. }) => {
-                    user_error!("Missing shortut or path to <-add> or <-edit>");
-                    String::new()
-                }
-
-                Cmd::Add(ShortPath {
-                    short: Some(short),
-                    path: Some(path),
-                }) => add(&dirline, success, short.as_str(), path),
-                Cmd::Edit(ShortPath {
-                    short: Some(short),
-                    path: Some(path),
-                }) => edit(&dirline, success, short.as_str(), path),
-            }
-        } else {
-            #[allow(clippy::print_stderr)]
-            {
-                eprintln!("[Internal Error] No option pushed in argument list.");
-            };
-            return String::new();
-        };
+    dirline.join(";")
+}
 
-        if line2.is_empty() {
-            String::new()
-        } else {
-            format!("{line2}\n")
-        }
+/// Function to rename a shortcut in a line of the directory file.
+/// # Arguments
+/// * `dirline` - The line of the directory file
+/// * `success` - A mutable reference to a boolean to indicate if the shortcut was renamed
+/// * `old` - The shortcut to rename
+/// * `new` - The new name of the shortcut
+/// # Returns
+/// The line of the directory file.
+/// # Note
+/// The uniqueness of `new` is checked before the rewrite, by `check_replace`.
+fn replace(dirline: &DirsLine, success: &mut bool, old: &str, new: &str) -> String {
+    if dirline.shorts.contains(&old) {
+        *success = true;
+        format!(
+            "{};{};{}{}",
+            dirline.path,
+            dirline
+                .shorts
+                .iter()
+                .map(|&sh| if sh == old { new } else { sh })
+                .collect::<Vec<_>>()
+                .join(";"),
+            dirline.priory,
+            dirline.marker()
+        )
+    } else {
+        dirline.join(";")
     }
 }
 
-/// Function to read the directory file
+/// Function to change the path of a directory in a line of the directory file.
+/// # Arguments
+/// * `dirline` - The line of the directory file
+/// * `success` - A mutable reference to a boolean to indicate if the path was changed
+/// * `sstate` - The state of the search, with the line to merge if `new` already has a line (see `check_move`)
+/// * `old` - The path to change
+/// * `new` - The new path
+/// # Returns
+/// The line of the directory file, empty if it is merged into the line of `new`.
+/// # Note
+/// When merging, the shortcuts of `old` are added to the line of `new`, which keeps the highest of the two priorities.
+fn move_path(
+    dirline: &DirsLine,
+    success: &mut bool,
+    sstate: &SearchState,
+    old: &str,
+    new: &str,
+) -> String {
+    match sstate.merged.as_ref() {
+        None if dirline.path == old => {
+            *success = true;
+            DirsLine {
+                path: new,
+                ..*dirline
+            }
+            .join(";")
+        }
+        Some(_) if dirline.path == old => String::new(),
+        Some((shorts, priory)) if dirline.path == new => {
+            let mut all = dirline.shorts.to_vec();
+            all.extend(
+                shorts
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|short| !dirline.shorts.contains(short)),
+            );
+            DirsLine {
+                shorts: &all,
+                priory: dirline.priory.max(*priory),
+                ..*dirline
+            }
+            .join(";")
+        }
+        _ => dirline.join(";"),
+    }
+}
+
+/// Function to read the lines of the directory file one at a time, without loading the whole file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// The lines of the file, without their line endings (none if the file can't be read, after printing an error).
+/// # Note
+/// A missing file is read as an empty list, without error, e.g. if it was removed since the start of the program.
+fn dir_lines(dpath: &str) -> impl Iterator<Item = String> + '_ {
+    fs::File::open(dpath)
+        .map_err(|er| {
+            if er.kind() != io::ErrorKind::NotFound {
+                file_error!("Unable to read file {dpath}: {er}");
+            }
+        })
+        .ok()
+        .map(io::BufReader::new)
+        .into_iter()
+        .flat_map(BufRead::lines)
+        .map_while(move |line| {
+            line.map_err(|er| file_error!("Unable to read a line of {dpath}: {er}"))
+                .ok()
+        })
+}
+
+/// Function to get all the shortcuts of the directory file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// The shortcuts of the valid lines (see `split_dline`).
+fn stored_shorts(dpath: &str) -> Vec<String> {
+    dir_lines(dpath)
+        .flat_map(|dline| {
+            split_dline(&dline).map_or_else(Vec::new, |(_, line_shorts, _)| {
+                line_shorts.into_iter().map(str::to_owned).collect()
+            })
+        })
+        .collect()
+}
+
+/// Function to apply the command to each line of the directory file (see `read_dline`).
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `args`, `success`, `gdata`, `sstate` - See `read_dline`
+/// # Returns
+/// The new lines of the file, and `true` if a line was modified or removed.
+/// # Note
+/// The file is read one line at a time (see `dir_lines`), so that only the new lines are kept in memory.
+fn read_dlines(
+    dpath: &str,
+    args: &[Cmd],
+    success: &mut bool,
+    gdata: &GlobalData,
+    sstate: &mut SearchState,
+) -> (String, bool) {
+    let mut data = String::new();
+    let mut changed = false;
+    for dline in dir_lines(dpath) {
+        let new_dline = read_dline(dline.trim(), args, success, gdata, sstate);
+        changed |= new_dline.strip_suffix('\n') != Some(dline.as_str());
+        data.push_str(&new_dline);
+    }
+    (data, changed)
+}
+
+/// Function to write the directory file, after saving its previous content for `-undo`.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `data` - The new content of the file
+/// * `cmd` - The command that modified the file
+/// # Note
+/// The previous content is saved in `dirs.csv.undo`, unless the command only navigates to a directory (which increments its priority), so that `-undo` reverts the last command that modified the list.
+/// Nothing is saved if the file doesn't exist yet.
+fn write_dirs(dpath: &str, data: &str, cmd: Option<&Cmd>) {
+    if !matches!(cmd, Some(Cmd::Get(_))) && path::Path::new(dpath).exists() {
+        let snapshot = format!("{dpath}.undo");
+        fs::copy(dpath, &snapshot).map(drop).write_error(&snapshot);
+    }
+    write_atomic(dpath, data).write_error(dpath);
+}
+
+/// Function to remove all the directories of the directory file (with `-clear`).
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Note
+/// The previous content can be restored with `-undo` (see `write_dirs`).
+pub fn clear(dpath: &str) {
+    match lock(dpath) {
+        Ok(_guard) => write_dirs(dpath, "", None),
+        Err(er) => file_error!("Unable to lock {dpath}: {er}"),
+    }
+}
+
+/// Function to restore the directory file as it was before the last command that modified it.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// `None`.
+/// # Note
+/// Only one command can be undone: the saved content (see `write_dirs`) is removed once restored.
+/// The priorities incremented since then by the navigation are restored too.
+pub fn undo(dpath: &str) -> ! {
+    let snapshot = format!("{dpath}.undo");
+    {
+        let Ok(_guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+        else {
+            #[expect(
+                clippy::exit,
+                reason = "the process ends with the status of the command"
+            )]
+            process::exit(1);
+        };
+        if path::Path::new(&snapshot).exists() {
+            fs::rename(&snapshot, dpath).write_error(dpath);
+            #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+            {
+                eprintln!("Restored {dpath} as it was before the last command");
+            };
+        } else {
+            user_error!("Nothing to undo: {snapshot} not found");
+        }
+    }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to choose the file of shortcuts to read and modify.
 /// # Arguments
-/// * `dpath` - The path of the directory file
 /// * `args` - The arguments of the command
-/// * `incr` - The increment value
+/// * `gdata` - The static data of the program
+/// # Returns
+/// The file given to `-add --to`, the first shared file with `--global` (see `Scope`), or else the personal file.
+/// `None` after printing an error if `--global` is given but no shared file is set.
+pub fn target_file<'file>(args: &'file [Cmd], gdata: &'file GlobalData) -> Option<&'file str> {
+    match args.first() {
+        Some(Cmd::Add(ShortsPath { to: Some(to), .. }, _)) if !to.is_empty() => Some(to),
+        Some(
+            Cmd::Add(
+                ShortsPath {
+                    scope: Scope::Global,
+                    ..
+                },
+                _,
+            )
+            | Cmd::Edit(_, Scope::Global),
+        ) => gdata.shared.first().map(String::as_str).or_else(|| {
+            user_error!("No global file of shortcuts for <--global>: set GOTO_DIRS_PATH");
+            None
+        }),
+        _ => Some(&gdata.dirs),
+    }
+}
+
+/// Function to check that a command can be run before reading the file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `cmd` - The command to run
+/// * `sstate` - The state of the search (see `check_move`)
+/// # Returns
+/// `false` after printing an error if the command can't be run (see `check_replace` and `check_move`).
+fn check_cmd(dpath: &str, cmd: Option<&Cmd>, sstate: &mut SearchState) -> bool {
+    match cmd {
+        Some(Cmd::Replace(ShortPair {
+            old: Some(old),
+            new: Some(new),
+        })) => check_replace(dpath, old, new),
+        Some(Cmd::Move(old, Some(new))) => check_move(dpath, old, new, sstate),
+        Some(Cmd::Add(ShortsPath { to: Some(to), .. }, _)) if to.is_empty() => {
+            user_error!("Missing file to <--to>");
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Function to go on with the search in the shared files of shortcuts (see `GlobalData::shared`).
+/// # Arguments
+/// * `args` - The arguments of the command
+/// * `success` - A mutable reference to a boolean to indicate if the shortcut was found
+/// * `gdata` - The static data of the program
+/// * `sstate` - The state of the search, shared with the personal file
+/// # Note
+/// The shared files are only searched to navigate, if the personal file has no exact match, and their lines are only read.
+fn search_shared(args: &[Cmd], success: &mut bool, gdata: &GlobalData, sstate: &mut SearchState) {
+    if !*success && matches!(args.first(), Some(Cmd::Get(_))) {
+        for shared in &gdata.shared {
+            read_dlines(shared, args, success, gdata, sstate);
+        }
+    }
+}
+
+/// Function to check that a shortcut can be renamed.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `old` - The shortcut to rename
+/// * `new` - The new name of the shortcut
+/// # Returns
+/// `true` if `old` exists and `new` doesn't exist in the whole file.
+fn check_replace(dpath: &str, old: &str, new: &str) -> bool {
+    let shorts = stored_shorts(dpath);
+    if !shorts.iter().any(|short| short == old) {
+        user_error!("Failed to replace shortcut {old}: not found");
+        false
+    } else if shorts.iter().any(|short| short == new) {
+        user_error!("Shortcut {new} already exists");
+        false
+    } else {
+        true
+    }
+}
+
+/// Function to check that the path of a directory can be changed.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `old` - The path to change
+/// * `new` - The new path
+/// * `sstate` - The state of the search, to save the line of `old` if `new` already has a line
+/// # Returns
+/// `true` if `old` has a line in the file.
+fn check_move(dpath: &str, old: &str, new: &str, sstate: &mut SearchState) -> bool {
+    let mut moved = None;
+    let mut merge = false;
+    for dline in dir_lines(dpath) {
+        match split_dline(dline.trim()) {
+            Some((path, shorts, priory)) if path == old => {
+                moved = Some((
+                    shorts.iter().map(|short| (*short).to_owned()).collect(),
+                    priory,
+                ));
+            }
+            Some((path, ..)) if path == new => merge = true,
+            _ => (),
+        }
+    }
+    if moved.is_none() {
+        user_error!("Failed to move {}: not found", unescape_path(old));
+        false
+    } else {
+        sstate.merged = moved.filter(|_| merge);
+        true
+    }
+}
+
+/// Function to check that a new shortcut can be used.
+/// # Arguments
+/// * `short` - The new shortcut
+/// * `gdata` - The static data of the program
+/// # Returns
+/// `false` after printing an error if the shortcut contains a forbidden character (see `FORBIDDEN`), or would be read as a command (see `GlobalData::reserved`).
+fn valid_short(short: &str, gdata: &GlobalData) -> bool {
+    if short.contains(FORBIDDEN) {
+        user_error!(
+            "Shortcut {} is invalid: it contains <;>, </>, <\\> or a newline",
+            short.escape_debug()
+        );
+        false
+    } else if gdata.reserved(short) {
+        user_error!(
+            "Shortcut {short} is reserved: it starts with <->, or is the name of a command"
+        );
+        false
+    } else {
+        true
+    }
+}
+
+/// Function to find the new shortcuts that can't be used.
+/// # Arguments
+/// * `args` - The arguments of the command
+/// * `gdata` - The static data of the program
+/// # Returns
+/// The new shortcuts of `-add` that are invalid (see `valid_short`), after printing an error for each.
+/// `None` if the new shortcut of `-clone` or `-replace-shortcut` is invalid, as there is nothing left to do.
+fn invalid_shorts(args: &[Cmd], gdata: &GlobalData) -> Option<Vec<String>> {
+    let invalid = match args.first() {
+        Some(Cmd::Add(shorts_path, _)) => shorts_path.shorts().iter().collect(),
+        Some(
+            Cmd::Clone(ShortPair { new: Some(new), .. })
+            | Cmd::Replace(ShortPair { new: Some(new), .. }),
+        ) => vec![new],
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|short| !valid_short(short, gdata))
+    .cloned()
+    .collect::<Vec<_>>();
+    (invalid.is_empty() || matches!(args.first(), Some(Cmd::Add(..)))).then_some(invalid)
+}
+
+/// Function to check that the directory given to `-add` exists.
+/// # Arguments
+/// * `args` - The arguments of the command
+/// # Returns
+/// `false` if the directory doesn't exist and `--allow-missing` wasn't given, after printing an error.
+fn existing_path(args: &[Cmd]) -> bool {
+    let Some(Cmd::Add(shorts_path, _)) = args.first() else {
+        return true;
+    };
+    let Some(path) = shorts_path.path() else {
+        return true;
+    };
+    let exists = shorts_path.allow_missing || path::Path::new(&unescape_path(&path)).exists();
+    if !exists {
+        user_error!(
+            "Directory {} doesn't exist: use <--allow-missing> to add it anyway",
+            unescape_path(&path)
+        );
+    }
+    exists
+}
+
+///////////////////////////////: command keywords functions  :///////////////////////////////
+
+/// Function to apply the command to a line of the directory file (see `read_dline`).
+/// # Arguments
+/// * `first` - The command
+/// * `dirline` - The line of the directory file
+/// * `success` - A mutable reference to a boolean to indicate if the command was successful
+/// * `gdata` - The static data of the program
+/// * `sstate` - A mutable reference to the state of the search
 /// # Returns
-/// The path of the directory
+/// The new line, without the line break (empty if the line is removed).
+fn update_dline(
+    first: &Cmd,
+    dirline: &DirsLine,
+    success: &mut bool,
+    gdata: &GlobalData,
+    sstate: &mut SearchState,
+) -> String {
+    match first {
+        Cmd::Get(ShortPath { short: None, .. }) => get(dirline, success, sstate, "", gdata),
+        Cmd::Get(ShortPath {
+            short: Some(short), ..
+        }) => get(dirline, success, sstate, short, gdata),
+        Cmd::Reset => DirsLine {
+            priory: 0,
+            ..*dirline
+        }
+        .join(";"),
+        Cmd::Age(factor) => DirsLine {
+            priory: aged(dirline.priory, factor.unwrap_or(AGE_FACTOR)),
+            ..*dirline
+        }
+        .join(";"),
+        Cmd::Decr(Some(short), _) if !dirline.shorts.contains(&short.as_str()) => dirline.join(";"),
+        Cmd::Decr(opt_short, Some(decr)) => {
+            *success = opt_short.is_some();
+            DirsLine {
+                priory: dirline.priory.saturating_sub(*decr),
+                ..*dirline
+            }
+            .join(";")
+        }
+        Cmd::Prune(broken_only, dry_run)
+            if (*broken_only && dirline.marks.broken)
+                || (!*broken_only && !path::Path::new(&unescape_path(dirline.path)).exists()) =>
+        {
+            sstate.pruned.push(unescape_path(dirline.path));
+            if *dry_run {
+                dirline.join(";")
+            } else {
+                String::new()
+            }
+        }
+
+        Cmd::Rm(shorts) => remove(dirline, success, sstate, shorts, gdata.case_sensitive),
+        Cmd::Del(path) if *dirline.path == *path || dirline.shorts.contains(&path.as_str()) => {
+            *success = true;
+            String::new()
+        }
+        Cmd::Freeze(short) | Cmd::Unfreeze(short) if dirline.shorts.contains(&short.as_str()) => {
+            *success = true;
+            DirsLine {
+                marks: Marks {
+                    frozen: matches!(first, Cmd::Freeze(_)),
+                    ..dirline.marks
+                },
+                ..*dirline
+            }
+            .join(";")
+        }
+        Cmd::Set(short, Some(new_priory)) if dirline.shorts.contains(&short.as_str()) => {
+            *success = true;
+            DirsLine {
+                priory: *new_priory,
+                ..*dirline
+            }
+            .join(";")
+        }
+
+        Cmd::Add(shorts_path, _) => shorts_path.path().map_or_else(
+            || {
+                user_error!("Missing shortut or path to <-add> or <-edit>");
+                String::new()
+            },
+            |path| add(dirline, success, sstate, shorts_path.shorts(), &path),
+        ),
+        Cmd::Edit(ShortPath { short: None, .. } | ShortPath { path: None, .. }, _) => {
+            user_error!("Missing shortut or path to <-add> or <-edit>");
+            String::new()
+        }
+        Cmd::Edit(
+            ShortPath {
+                short: Some(short),
+                path: Some(path),
+            },
+            _,
+        ) => edit(dirline, success, short.as_str(), path),
+
+        Cmd::Clone(ShortPair {
+            old: Some(old),
+            new: Some(new),
+        }) => clone_short(dirline, success, sstate, old, new),
+
+        Cmd::Replace(ShortPair {
+            old: Some(old),
+            new: Some(new),
+        }) => replace(dirline, success, old, new),
+
+        Cmd::Move(old, Some(new)) => move_path(dirline, success, sstate, old, new),
+        // The lines without the shortcut, and the commands missing a value, keep the line
+        Cmd::Decr(_, None)
+        | Cmd::Prune(..)
+        | Cmd::Del(_)
+        | Cmd::Freeze(_)
+        | Cmd::Unfreeze(_)
+        | Cmd::Set(..)
+        | Cmd::Clone(_)
+        | Cmd::Replace(_)
+        | Cmd::Move(..) => dirline.join(";"),
+    }
+}
+
+/// Function to read a line of the directory file.
+/// # Arguments
+/// * `rdline` - The line of the directory file
+/// * `args` - The arguments of the command
+/// * `success` - A mutable reference to a boolean to indicate if the command was successful
+/// * `gdata` - The static data of the program
+/// * `sstate` - A mutable reference to the state of the search
+/// # Returns
+/// The line of the directory file.
 /// # Example
-/// ```
-/// let dpath = "/home/user/.dirs";
+/// ```ignore
+/// let mut success = false;
+/// let mut sstate = SearchState::default();
+/// let rdline = "/home/user/folder;f;folder;1";
 /// let args = vec!["get".to_string(), "f".to_string()];
-/// let path = read(&dpath, &args, 1);
+/// let line = read_dline(&rdline, &args, &mut success, &GlobalData::default(), &mut sstate);
+/// ```
+/// # Panics
+/// If the command is invalid.
+fn read_dline(
+    rdline: &str,
+    args: &[Cmd],
+    success: &mut bool,
+    gdata: &GlobalData,
+    sstate: &mut SearchState,
+) -> String {
+    if *success {
+        if rdline.trim().is_empty() {
+            return String::new();
+        }
+        return format!("{rdline}\n");
+    }
+    let mut vecline: Vec<&str> = split_line(rdline);
+    let marks = pop_markers(&mut vecline);
+    if vecline.len() < 2 {
+        assert!(
+            vecline.first().unwrap_or(&"").is_empty(),
+            "Invalid rdline {rdline} found in directory library"
+        );
+        String::new()
+    } else {
+        #[expect(
+            clippy::expect_used,
+            reason = "the lines of the file have at least one field"
+        )]
+        let priory = vecline
+            .last()
+            .expect("[Data Error] Missing priority in dline.")
+            .parse::<u32>()
+            .data_error(format!("Priority not an integer in {rdline}"), None);
+
+        let dirline = DirsLine {
+            path: if let Some(pth) = vecline.first() {
+                pth
+            } else {
+                return String::new();
+            },
+            shorts: vecline
+                .get(
+                    1..vecline
+                        .len()
+                        .checked_sub(1)
+                        .data_error("Unable to subtract to priority", None),
+                )
+                .data_error("Missing values in line", None),
+            priory,
+            priory2: priory.saturating_add(gdata.incr).min(gdata.max_priority),
+            marks,
+        };
+
+        let line2 = if let Some(first) = args.first() {
+            update_dline(first, &dirline, success, gdata, sstate)
+        } else {
+            #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+            {
+                eprintln!("[Internal Error] No option pushed in argument list.");
+            };
+            return String::new();
+        };
+
+        if line2.is_empty() {
+            String::new()
+        } else {
+            format!("{line2}\n")
+        }
+    }
+}
+
+/// Function to join a subdirectory to the directory of a shortcut.
+/// # Arguments
+/// * `base` - The directory of the shortcut, ending with a `/`
+/// * `path` - The subdirectory given after the shortcut (e.g. `src/module` in `. gt proj src/module`)
+/// # Returns
+/// The joined path, or `base` if the joined path doesn't exist.
+fn sub_dir(base: String, path: Option<&str>) -> String {
+    let Some(sub) = path
+        .map(|sub| sub.trim_start_matches('/'))
+        .filter(|sub| !sub.is_empty())
+    else {
+        return base;
+    };
+    let joined = format!("{base}{sub}");
+    if path::Path::new(&joined).is_dir() {
+        joined
+    } else {
+        user_error!("Directory {joined} not found: staying in {base}");
+        base
+    }
+}
+
+/// Function to multiply a priority by the factor of `-age`.
+/// # Arguments
+/// * `priory` - The priority of a line
+/// * `factor` - The factor, between 0 and 1
+/// # Returns
+/// The priority multiplied by the factor, rounded down.
+/// # Example
+/// ```ignore
+/// assert!(aged(15, 0.9) == 13);
+/// ```
+fn aged(priory: u32, factor: f64) -> u32 {
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::as_conversions,
+        clippy::float_arithmetic,
+        reason = "the priority times a factor between 0 and 1 fits in a `u32`"
+    )]
+    let priory_aged = (f64::from(priory) * factor).floor() as u32;
+    priory_aged
+}
+
+/// Function to halve the priorities of the directory file, if one of them reached the maximum.
+/// # Arguments
+/// * `data` - The lines of the directory file
+/// * `max_priority` - The maximum priority (see `GlobalData`)
+/// # Returns
+/// The lines with their priorities halved, or `None` if no priority reached `max_priority`.
+/// # Note
+/// Halving all the priorities keeps the order of the directories, and leaves room for the priorities to grow again.
+fn rescale(data: &str, max_priority: u32) -> Option<String> {
+    let lines = data
+        .lines()
+        .map(|dline| {
+            let mut vecline = split_line(dline);
+            let marks = pop_markers(&mut vecline);
+            let split = vecline.split_last().and_then(|(priory, rest)| {
+                priory
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|_| rest.len() >= 2)
+                    .map(|priory_int| (rest.join(";"), priory_int))
+            });
+            (dline, split, marks)
+        })
+        .collect::<Vec<_>>();
+    lines
+        .iter()
+        .any(|(_, split, _)| {
+            split
+                .as_ref()
+                .is_some_and(|(_, priory)| *priory >= max_priority)
+        })
+        .then(|| {
+            lines
+                .iter()
+                .fold(String::new(), |mut acc, (dline, split, marks)| {
+                    match split {
+                        Some((rest, priory)) => writeln!(
+                            acc,
+                            "{rest};{}{}",
+                            priory.checked_div(2).unwrap_or_default(),
+                            marks.end()
+                        ),
+                        None => writeln!(acc, "{dline}"),
+                    }
+                    .write_error("lines");
+                    acc
+                })
+        })
+}
+
+/// Function to insert a new line in the directory file, after the line of a shortcut.
+/// # Arguments
+/// * `data` - The lines of the directory file
+/// * `after` - The shortcut after whose line the new line is inserted
+/// * `dline` - The new line
+/// # Returns
+/// The lines of the directory file with the new line, or `None` if the shortcut wasn't found.
+/// # Example
+/// ```ignore
+/// let data = insert_after("/home;h;1\n/tmp;t;0\n", "h", "/usr;u;0");
+/// assert!(data == Some(String::from("/home;h;1\n/usr;u;0\n/tmp;t;0\n")));
+/// ```
+fn insert_after(data: &str, after: &str, dline: &str) -> Option<String> {
+    let mut found = false;
+    let mut res = String::new();
+    for line in data.lines() {
+        res.push_str(line);
+        res.push('\n');
+        if !found && split_dline(line).is_some_and(|(_, shorts, _)| shorts.contains(&after)) {
+            found = true;
+            res.push_str(dline);
+            res.push('\n');
+        }
+    }
+    found.then_some(res)
+}
+
+/// Function to print how a shortcut is resolved, for `-explain`.
+/// # Arguments
+/// * `sstate` - The state of the search
+/// * `dpath` - The path of the directory file, for the priorities of the paths
+/// * `gdata` - The static data of the program, for the order of the tiers
+/// * `cmd` - The command of the search, expected to be `Cmd::Get`
+/// # Note
+/// The strategy that found the directory is printed, with its path and priority, followed by the directories found by the other strategies.
+/// The strategies are listed in the order in which they are tried in `read` (the menu of `GOTO_INTERACTIVE` is never shown).
+fn explain(sstate: &SearchState, dpath: &str, gdata: &GlobalData, cmd: Option<&Cmd>) {
+    let Some(Cmd::Get(ShortPath {
+        short: opt_short, ..
+    })) = cmd
+    else {
+        user_error!("<-explain> only explains how the directory of a shortcut is found");
+        return;
+    };
+    let short = opt_short.as_deref().filter(|shortcut| !shortcut.is_empty());
+    let mut candidates = vec![
+        ("exact", sstate.correct.as_deref()),
+        ("case-insensitive", sstate.case_insensitive.as_deref()),
+    ];
+    for tier in &gdata.tiers {
+        let path = match tier {
+            Tier::Exact => continue,
+            Tier::Prefix => sstate.prefix.as_deref(),
+            Tier::Fuzzy => sstate.fuzzy.as_deref(),
+            Tier::Basename => sstate.basename.as_deref(),
+        };
+        candidates.push((tier.name(), path));
+    }
+    if short.is_none() {
+        candidates.push(("most used", sstate.prioritised.as_deref()));
+    }
+    let describe = |path: &str| {
+        let priory = dir_lines(dpath)
+            .find_map(|dline| {
+                split_dline(&dline)
+                    .filter(|(pth, _, _)| *pth == path)
+                    .map(|(_, _, priory)| format!(" (priority {priory})"))
+            })
+            .unwrap_or_default();
+        format!("{}{priory}", unescape_path(path))
+    };
+    let mut found = candidates
+        .iter()
+        .filter_map(|(strategy, path)| path.map(|pth| (strategy, describe(pth))));
+    let mut explanation = match (found.next(), short) {
+        (Some((strategy, path)), _) => format!("Found by {strategy}: {path}\n"),
+        (None, Some(shortcut)) => format!("No directory found for {shortcut}\n"),
+        (None, None) => String::from("No directory found\n"),
+    };
+    for (strategy, path) in found {
+        writeln!(explanation, "Runner-up by {strategy}: {path}").write_error("explanation");
+    }
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{explanation}");
+    };
+}
+
+/// Report a command of `read` that didn't succeed, or add the line of the commands that create one (e.g. `-add`).
+/// # Arguments
+/// * `arg` - The command
+/// * `data` - The lines of the file, to which the new line is added
+/// * `sstate` - The state of the search, with the shortcuts found
+fn report_failure(arg: &Cmd, data: &mut String, sstate: &SearchState) {
+    match arg {
+        Cmd::Decr(_, None) => user_error!("Missing amount to <-decrement>"),
+        Cmd::Decr(Some(short), Some(_)) => {
+            user_error!("Failed to decrement {short}: not found");
+        }
+        Cmd::Prune(_, dry_run) => print_pruned(&sstate.pruned, *dry_run),
+        // `check_move` already made sure that the path exists, and `-get` is handled by `read`
+        Cmd::Get(_)
+        | Cmd::Reset
+        | Cmd::Age(_)
+        | Cmd::Decr(None, Some(_))
+        | Cmd::Move(_, Some(_)) => {}
+        Cmd::Edit(ShortPath { path: None, .. }, _) => {
+            user_error!("Shortcut not found, and missing path to <-edit>");
+        }
+        Cmd::Add(_, Some(after)) if after.is_empty() => {
+            user_error!("Missing shortcut to <--after>");
+        }
+        Cmd::Add(shorts_path, opt_after) => match (shorts_path.path(), opt_after) {
+            (None, _) => user_error!("Missing path to <-add>"),
+            (Some(path), Some(after)) => {
+                let shorts = new_only(shorts_path.shorts(), &sstate.existing);
+                match insert_after(data, after, &format!("{path};{shorts};0")) {
+                    Some(new_data) => *data = new_data,
+                    None => {
+                        user_error!("Failed to add shortcut {shorts} after {after}: not found");
+                    }
+                }
+            }
+            (Some(path), None) => {
+                let shorts = new_only(shorts_path.shorts(), &sstate.existing);
+                write!(data, "{path};{shorts};0").write_error("Lines");
+            }
+        },
+        Cmd::Edit(
+            ShortPath {
+                short: opt_short,
+                path: Some(path),
+            },
+            _,
+        ) => match opt_short.as_ref() {
+            Some(short) => write!(data, "{path};{short};0").write_error("Lines"),
+            None => user_error!("Missing shortut to add"),
+        },
+
+        Cmd::Rm(shorts) => shorts
+            .iter()
+            .filter(|short| !sstate.removed.contains(short))
+            .for_each(|short| user_error!("Failed to remove shortcut {short}: not found")),
+        Cmd::Del(path) => user_error!("Failed to delete {path}: no such path or shortcut"),
+        Cmd::Freeze(short) | Cmd::Unfreeze(short) | Cmd::Set(short, Some(_)) => {
+            user_error!("Failed to change the priority of {short}: not found");
+        }
+        Cmd::Set(_, None) => user_error!("Missing priority to <-set>"),
+        Cmd::Clone(ShortPair {
+            old: Some(_),
+            new: Some(new),
+        }) => match sstate.correct.as_ref() {
+            Some(path) => write!(data, "{path};{new};0").write_error("Lines"),
+            None => user_error!("Failed to clone shortcut: not found"),
+        },
+        Cmd::Clone(_) => user_error!("Missing shortcut to <-clone>"),
+        Cmd::Replace(ShortPair {
+            old: Some(_),
+            new: Some(_),
+        }) => user_error!("Failed to replace shortcut: not found"),
+        Cmd::Replace(_) => user_error!("Missing shortcut to <-replace-shortcut>"),
+        Cmd::Move(_, None) => user_error!("Missing path to <-move>"),
+    }
+}
+
+/// Find the shortcuts given to `-add` or `-rm` that are already in the file, and skip the existing and invalid ones of `-add`.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `arg` - The first command
+/// * `invalid` - The invalid shortcuts given to `-add` (see `invalid_shorts`)
+/// * `sstate` - The state of the search, in which the existing shortcuts are stored
+/// # Returns
+/// `true` if `-add` has nothing left to add.
+fn skip_existing(
+    dpath: &str,
+    arg: Option<&Cmd>,
+    invalid: Vec<String>,
+    sstate: &mut SearchState,
+) -> bool {
+    let given = match arg {
+        Some(Cmd::Rm(shorts)) => shorts.as_slice(),
+        Some(Cmd::Add(shorts_path, _)) => shorts_path.shorts(),
+        _ => &[],
+    };
+    if !given.is_empty() {
+        let stored = stored_shorts(dpath);
+        sstate.existing = given
+            .iter()
+            .filter(|short| stored.contains(short))
+            .cloned()
+            .collect();
+    }
+    if !matches!(arg, Some(Cmd::Add(..))) {
+        return false;
+    }
+    for short in &sstate.existing {
+        user_error!("Shortcut {short} already exists");
+    }
+    // The invalid shortcuts are skipped like the existing ones
+    let skipped = invalid
+        .into_iter()
+        .filter(|short| !sstate.existing.contains(short))
+        .collect::<Vec<_>>();
+    sstate.existing.extend(skipped);
+    // Nothing to add
+    sstate.existing.len() == given.len()
+}
+
+/// Function to read the directory file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `args` - The arguments of the command
+/// * `gdata` - The static data of the program
+/// * `force` - `true` to reset the priorities without confirmation (see `confirm`)
+/// * `explain` - `true` to print how the shortcut is resolved (see `explain`), instead of going to the directory and updating the file
+/// # Returns
+/// The path of the directory.
+/// # Example
+/// ```no_run
+/// use goto::{dirs, GlobalData};
+/// let gdata = GlobalData::default();
+/// let (args, _, _) = goto::parse_args(&gdata, &[String::from("f")]).unwrap_or_default();
+/// let path = dirs::read("/home/user/.dirs", &args, &gdata, false, false);
+/// ```
+/// # Note
+/// The path is the one of the shortcut if found, else the one found by the first successful tier (see `Tier`), else the most used one if no shortcut was given.
+/// The `exact` tier, if enabled, is always tried first, as it stops the search and increments the usage of the directory.
+/// The file is only written if its content changed (e.g. not when the shortcut isn't found).
+/// A missing file is read as an empty one, and is created with its folder by the first command that writes it (e.g. `-add`).
+///
+pub fn read(
+    dpath: &str,
+    args: &[Cmd],
+    gdata: &GlobalData,
+    force: bool,
+    explain: bool,
+) -> Option<String> {
+    let mut sstate = SearchState {
+        now: time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .internal_error("Time went backwards.", None)
+            .as_secs(),
+        ..SearchState::default()
+    };
+
+    if matches!(args.first(), Some(Cmd::Reset)) && !confirm(dpath, "reset the priorities of", force)
+    {
+        return None;
+    }
+
+    // A missing file is an empty one, but its folder is needed for the lock (see `GlobalData::create_files`)
+    if let Some(parent) = path::Path::new(dpath)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty() && !parent.exists())
+    {
+        fs::create_dir_all(parent).write_error(dpath);
+    }
+    // Held until the file is written, so that another process doesn't read it in between
+    let Ok(guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+    else {
+        return None;
+    };
+    if !check_cmd(dpath, args.first(), &mut sstate) {
+        return None;
+    }
+
+    let Some(invalid) = invalid_shorts(args, gdata).filter(|_| existing_path(args)) else {
+        return None;
+    };
+
+    let mut success = skip_existing(dpath, args.first(), invalid, &mut sstate);
+
+    let (mut data, mut changed) = read_dlines(dpath, args, &mut success, gdata, &mut sstate);
+    search_shared(args, &mut success, gdata, &mut sstate);
+    let streamed = data.len();
+
+    // dbg!(&sstate);
+
+    // The most used directory is only a fallback when no shortcut is given
+    let no_short = !matches!(args.first(), Some(Cmd::Get(ShortPath { short: Some(short), .. })) if !short.is_empty());
+    // With `-explain`, nothing is written and the directory isn't returned
+    if explain {
+        self::explain(&sstate, dpath, gdata, args.first());
+        return None;
+    }
+    let mut some = false;
+    let mut here = None;
+    // The sub-directory to go to in the directory found (see `sub_dir`)
+    let mut sub = None;
+
+    for arg in args {
+        // Cmd::Get(ShortPath {
+        //     short: Some(short), ..
+        // }) if !success => {
+        //     some = false;
+        //     res = None;
+        // }
+        if let Cmd::Get(ShortPath { short, path }) = arg {
+            some = true;
+            if here.is_none() {
+                here.clone_from(short);
+            } else {
+                user_error!("Multiple <-get> commands.");
+            }
+            if success {
+                sub = Some(path.as_deref());
+            }
+        } else if !success {
+            report_failure(arg, &mut data, &sstate);
+        } else {
+            // Nothing to report
+        }
+    }
+
+    // Once a priority reaches the maximum, all of them are halved so that they can keep growing
+    let rescaled = rescale(&data, gdata.max_priority);
+    // The commands that add a line change the length of the file
+    changed |= data.len() != streamed || rescaled.is_some();
+    data = rescaled.unwrap_or(data);
+
+    // With `-prune --dry-run`, or if nothing changed, the file is left untouched
+    let dry_run = matches!(args.first(), Some(Cmd::Prune(_, true)));
+    (!dry_run && changed).then(|| write_dirs(dpath, &data, args.first()));
+    // The user may take some time to choose a directory (see `choose_prefix`): the lock is released before
+    drop(guard);
+
+    let mut res = sstate
+        .correct
+        .clone()
+        .or_else(|| sstate.case_insensitive.clone())
+        .or_else(|| choose_prefix(&sstate.prefixes))
+        .or_else(|| {
+            gdata.tiers.iter().find_map(|tier| match tier {
+                Tier::Exact => None,
+                Tier::Prefix => sstate.prefix.clone(),
+                Tier::Fuzzy => sstate.fuzzy.clone(),
+                Tier::Basename => sstate.basename.clone(),
+            })
+        })
+        .or_else(|| sstate.prioritised.filter(|_| no_short))
+        .map(|x| {
+            let mut result = unescape_path(&x);
+            result.push('/');
+            result
+        });
+    if let Some(path) = sub {
+        res = res.map(|base| sub_dir(base, path));
+    }
+
+    // dbg!(&some, &here, &res);
+    if some {
+        match here {
+            Some(local) if !local.is_empty() && path::Path::new(&local).exists() => Some(local),
+            Some(local) if res.is_none() => {
+                user_error!("Shortcut {local} not found");
+                None
+            }
+            _ => res,
+        }
+    } else {
+        None
+    }
+}
+
+/// Function to format the state of the directories.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `by_mtime` - `true` to sort the directories by modification time on the disk (most recent first)
+/// * `by_priority` - `true` to sort the directories by priority (highest first)
+/// # Returns
+/// The aligned table of the directories, to be printed by the caller.
+/// # Panics
+/// If the file is not found.
+/// # Note
+/// The text will be formatted in the following format (`✗` marks the paths found not to exist):
+/// ```text
+/// afirstpath  shortut1 shortut2 14
+/// asecondpath short1 s2 s3 s4 14 ✗
+/// third       afirstshortut     14
+/// ```
+/// When sorting by modification time, the directories that don't exist are printed at the end, with a note.
+/// If stdout is a terminal, the paths that don't exist are printed in red, and the directory with the highest priority in green.
+///
+pub fn state(dpath: &str, by_mtime: bool, by_priority: bool) -> String {
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut data = binding
+        .lines()
+        .map(|dline| {
+            let mut vecline = split_line(dline);
+            let broken = pop_markers(&mut vecline).broken;
+            let modified = if by_mtime {
+                vecline.first().and_then(|pth| {
+                    fs::metadata(unescape_path(pth))
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                })
+            } else {
+                None
+            };
+            (vecline, broken, modified)
+        })
+        // The lines that `align` can't print (e.g. the empty ones) are skipped, so that the table matches `paths` (see `paint`)
+        .filter(|(vecline, ..)| vecline.len() >= 2)
+        .collect::<Vec<_>>();
+    if by_mtime {
+        data.sort_by(|(_, _, fst), (_, _, snd)| snd.cmp(fst));
+    }
+    if by_priority {
+        data.sort_by_key(|(vecline, ..)| Reverse(priority(vecline)));
+    }
+    let paths = data
+        .iter()
+        .map(|(vecline, ..)| {
+            (
+                vecline.first().copied().unwrap_or_default(),
+                priority(vecline),
+            )
+        })
+        .collect::<Vec<_>>();
+    let lines = data
+        .into_iter()
+        .map(|(vecline, broken, modified)| {
+            let mark = if broken { BROKEN_MARK } else { "" };
+            let note = if by_mtime && modified.is_none() {
+                " (not found)"
+            } else {
+                ""
+            };
+            (vecline, format!("{mark}{note}"))
+        })
+        .collect::<Vec<_>>();
+    let table = align(&lines);
+    if io::stdout().is_terminal() {
+        paint(&table, &paths)
+    } else {
+        table
+    }
+}
+
+/// Function to get the priority of a split line of the directory file.
+/// # Returns
+/// The priority, or 0 if it isn't an integer.
+fn priority(vecline: &[&str]) -> u32 {
+    vecline
+        .last()
+        .and_then(|priory| priory.parse::<u32>().ok())
+        .unwrap_or_default()
+}
+
+/// Function to colour the lines of the state of the directories, with ANSI codes.
+/// # Arguments
+/// * `table` - The aligned table of the directories (see `state`)
+/// * `paths` - The paths and the priorities of the lines of the table, in the same order
+/// # Returns
+/// The table, with the paths that don't exist in red, and the first existing directory with the highest priority in green.
+fn paint(table: &str, paths: &[(&str, u32)]) -> String {
+    let exists = paths
+        .iter()
+        .map(|(pth, _)| path::Path::new(&unescape_path(pth)).exists())
+        .collect::<Vec<_>>();
+    let best = paths
+        .iter()
+        .zip(&exists)
+        .enumerate()
+        .rev()
+        .filter(|(_, (_, found))| **found)
+        .max_by_key(|(_, ((_, priory), _))| *priory)
+        .map(|(idx, _)| idx);
+    table.lines().zip(exists).enumerate().fold(
+        String::new(),
+        |mut painted, (idx, (line, found))| {
+            if !found {
+                writeln!(painted, "\x1b[31m{line}\x1b[0m")
+            } else if best == Some(idx) {
+                writeln!(painted, "\x1b[32m{line}\x1b[0m")
+            } else {
+                writeln!(painted, "{line}")
+            }
+            .write_error("lines");
+            painted
+        },
+    )
+}
+
+/// Function to list the shortcuts of the directory file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// The shortcuts, one per line (used by the completion scripts).
+pub fn shortcuts(dpath: &str) -> String {
+    fs::read_to_string(dpath)
+        .read_error(dpath, None)
+        .lines()
+        .filter_map(split_dline)
+        .flat_map(|(_, line_shorts, _)| line_shorts)
+        .fold(String::new(), |mut acc, short| {
+            writeln!(acc, "{short}").write_error("lines");
+            acc
+        })
+}
+
+/// Function to align the lines of the directory file in columns.
+/// # Arguments
+/// * `data` - The lines of the directory file, split on `;` (without the broken marker), with a text to print after the priority
+/// # Returns
+/// The aligned lines (see `state`).
+/// # Note
+/// The shortcuts of a line are aligned as a single column, so that the priorities are aligned whatever the number of shortcuts of each line.
+fn align(data: &[(Vec<&str>, String)]) -> String {
+    let columns = data
+        .iter()
+        .filter_map(|(dline, suffix)| match dline.as_slice() {
+            [path, shorts @ .., priory] => Some((*path, shorts.join(" "), *priory, suffix)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let path_width = columns
+        .iter()
+        .map(|(path, ..)| path.chars().count())
+        .max()
+        .unwrap_or_default();
+    let shorts_width = columns
+        .iter()
+        .map(|(_, shorts, ..)| shorts.chars().count())
+        .max()
+        .unwrap_or_default();
+    columns.iter().fold(
+        String::new(),
+        |mut state, (path, shorts, priory, suffix)| {
+            writeln!(
+                state,
+                "{path:<path_width$} {shorts:<shorts_width$} {priory}{suffix}"
+            )
+            .write_error("lines");
+            state
+        },
+    )
+}
+
+/// Function to print the directories that were never navigated to.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `hpath` - The path of the history file
+/// # Returns
+/// `None`.
+/// # Note
+/// The directories are printed like in `state`, if their path is not in the history file.
+pub fn unused(dpath: &str, hpath: &str) -> ! {
+    let hist = fs::read_to_string(hpath).read_error(hpath, None);
+    let visited = hist
+        .lines()
+        .filter_map(|hline| split_line(hline).first().copied())
+        .collect::<collections::HashSet<_>>();
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let lines = binding
+        .lines()
+        .filter(|dline| !dline.trim().is_empty())
+        .map(|dline| {
+            let mut vecline = split_line(dline);
+            let broken = pop_markers(&mut vecline).broken;
+            (vecline, String::from(if broken { BROKEN_MARK } else { "" }))
+        })
+        .filter(|(vecline, _)| vecline.first().is_some_and(|pth| !visited.contains(pth)))
+        .collect::<Vec<_>>();
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{}", align(&lines));
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to print statistics about the directory file.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// `None`.
+/// # Note
+/// The text will be printed in the following format:
+/// ```text
+/// Shortcuts: 12
+/// Paths: 10
+/// Priorities: 340 in total, 34 on average, 130 at most
+/// Dead paths: 1
+/// ```
+/// The dead paths are the ones that don't exist anymore (see `-prune`).
+pub fn stats(dpath: &str) -> ! {
+    let mut shorts_count: usize = 0;
+    let mut paths = collections::HashSet::new();
+    let mut lines: u64 = 0;
+    let mut total: u64 = 0;
+    let mut max: u32 = 0;
+    let mut dead: usize = 0;
+    for dline in dir_lines(dpath) {
+        if let Some((pth, shorts, priory)) = split_dline(&dline) {
+            shorts_count = shorts_count.saturating_add(shorts.len());
+            lines = lines.saturating_add(1);
+            total = total.saturating_add(u64::from(priory));
+            max = max.max(priory);
+            if paths.insert(pth.to_owned()) && !path::Path::new(&unescape_path(pth)).exists() {
+                dead = dead.saturating_add(1);
+            }
+        }
+    }
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("Shortcuts: {shorts_count}");
+        println!("Paths: {}", paths.len());
+        println!(
+            "Priorities: {total} in total, {} on average, {max} at most",
+            total.checked_div(lines).unwrap_or_default()
+        );
+        println!("Dead paths: {dead}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to print the directories whose path or a shortcut contains a term.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `term` - The term to look for (case-insensitive)
+/// # Returns
+/// `None`.
+/// # Note
+/// The directories are printed like in `state`. If stdout is a terminal, the occurrences of the term are highlighted.
+pub fn search(dpath: &str, term: &str) -> ! {
+    let lower = term.to_lowercase();
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let lines = binding
+        .lines()
+        .filter(|dline| !dline.trim().is_empty())
+        .map(|dline| {
+            let mut vecline = split_line(dline);
+            let broken = pop_markers(&mut vecline).broken;
+            (vecline, String::from(if broken { BROKEN_MARK } else { "" }))
+        })
+        .filter(|(vecline, _)| {
+            vecline.split_last().is_some_and(|(_, fields)| {
+                fields.iter().enumerate().any(|(idx, field)| {
+                    let text = if idx == 0 {
+                        unescape_path(field)
+                    } else {
+                        (*field).to_owned()
+                    };
+                    text.to_lowercase().contains(&lower)
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let aligned = align(&lines);
+    let found = if io::stdout().is_terminal() && !lower.is_empty() {
+        aligned.lines().fold(String::new(), |mut acc, line| {
+            writeln!(acc, "{}", highlight(line, &lower)).write_error("lines");
+            acc
+        })
+    } else {
+        aligned
+    };
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{found}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to highlight the occurrences of a term in a line, with ANSI codes.
+/// # Arguments
+/// * `line` - The line to highlight
+/// * `lower` - The term, in lowercase
+/// # Returns
+/// The line, with the occurrences of the term in bold yellow.
+/// # Note
+/// The line is returned as is if it has characters whose lowercase doesn't have the same length, as the occurrences can't be found in the line then.
+fn highlight(line: &str, lower: &str) -> String {
+    let lower_line = line.to_lowercase();
+    if lower_line.len() != line.len() {
+        return line.to_owned();
+    }
+    let mut highlighted = String::new();
+    let mut last = 0;
+    for (start, _) in lower_line.match_indices(lower) {
+        let end = start.saturating_add(lower.len());
+        write!(
+            highlighted,
+            "{}\x1b[1;33m{}\x1b[0m",
+            line.get(last..start).unwrap_or_default(),
+            line.get(start..end).unwrap_or_default()
+        )
+        .write_error("line");
+        last = end;
+    }
+    highlighted.push_str(line.get(last..).unwrap_or_default());
+    highlighted
+}
+
+/// Function to print the directories removed by `-prune`.
+/// # Arguments
+/// * `pruned` - The paths of the removed directories
+/// * `dry_run` - `true` if the directories were only listed, and not removed
+#[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+fn print_pruned(pruned: &[String], dry_run: bool) {
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for path in pruned {
+        eprintln!("{verb} {path}");
+    }
+    eprintln!("{verb} {} directories", pruned.len());
+}
+
+/// Function to back up the directory file, and ask the user to confirm a destructive command.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `action` - The description of the command, e.g. `clear`
+/// * `force` - `true` to skip the confirmation (with `-force`)
+/// # Returns
+/// `true` if the command can be run.
+/// # Note
+/// The directory file is first copied to `dirs.csv.bak`, even if the command is then aborted.
+/// Any answer but `y` or `yes` aborts the command (e.g. when stdin is not a terminal).
+pub fn confirm(dpath: &str, action: &str, force: bool) -> bool {
+    let backup = format!("{dpath}.bak");
+    fs::write(&backup, fs::read_to_string(dpath).read_error(dpath, None)).write_error(&backup);
+    if force {
+        return true;
+    }
+    #[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+    {
+        eprint!("Do you really want to {action} all the directories? A backup is saved in {backup} [y/N] ");
+    };
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .read_error("stdin", None);
+    let confirmed = matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+    if !confirmed {
+        user_error!("Aborted, {dpath} was not modified");
+    }
+    confirmed
+}
+
+/// Function to edit the directory file in a text editor.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// # Returns
+/// `None`.
+/// # Note
+/// The directory file is copied to a temporary file, which is opened in `$VISUAL` or `$EDITOR` (`vi` or `notepad` by default).
+/// Once the editor is closed, the lines are checked: if they are all valid and no shortcut is used twice, the temporary file replaces the directory file.
+/// Otherwise, the first error is printed, and the directory file is left untouched (the temporary file is kept to retrieve the edits).
+pub fn bulk_edit(dpath: &str) -> ! {
+    let tmp = env::temp_dir()
+        .join(format!("goto-{}.csv", process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let original = fs::read_to_string(dpath).read_error(dpath, None);
+    fs::write(&tmp, &original).write_error(&tmp);
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    match process::Command::new(program)
+        .args(words)
+        .arg(&tmp)
+        .status()
+    {
+        Ok(status) if status.success() => {
+            let edited = fs::read_to_string(&tmp).read_error(&tmp, None);
+            let mut shorts = collections::HashSet::new();
+            let error = edited.lines().enumerate().find_map(|(idx, dline)| {
+                let line_nb = idx.saturating_add(1);
+                check_dline(dline)
+                    .map_err(|er| format!("Line {line_nb} is invalid: {er}"))
+                    .and_then(|()| {
+                        split_dline(dline).map_or(Ok(()), |(_, line_shorts, _)| {
+                            line_shorts
+                                .into_iter()
+                                .find(|sh| !shorts.insert(*sh))
+                                .map_or(Ok(()), |sh| {
+                                    Err(format!("Line {line_nb}: shortcut {sh} already exists"))
+                                })
+                        })
+                    })
+                    .err()
+            });
+            // The file isn't locked while the editor is open: it is only written if it wasn't modified in between
+            let guard = lock(dpath);
+            match (error, guard) {
+                (Some(er), _) => {
+                    user_error!("{er}. {dpath} was not modified, the edits are kept in {tmp}");
+                }
+                (None, Err(er)) => {
+                    file_error!("Unable to lock {dpath}: {er}. It was not modified, the edits are kept in {tmp}");
+                }
+                (None, Ok(_)) if fs::read_to_string(dpath).read_error(dpath, None) != original => {
+                    user_error!("{dpath} was modified by another process while editing. It was not modified, the edits are kept in {tmp}");
+                }
+                (None, Ok(_)) => {
+                    write_dirs(dpath, &edited, None);
+                    fs::remove_file(&tmp).system_error("Unable to remove the temporary file");
+                }
+            }
+        }
+        Ok(status) => {
+            command_error!("{editor} failed ({status}), {dpath} was not modified");
+            fs::remove_file(&tmp).system_error("Unable to remove the temporary file");
+        }
+        Err(er) => {
+            command_error!("Unable to open {editor}: {er}");
+            fs::remove_file(&tmp).system_error("Unable to remove the temporary file");
+        }
+    }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to print the shortcuts sorted by priority, with their rank.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `limit` - The maximum number of lines to print (all the lines if `None`)
+/// # Returns
+/// `None`.
+/// # Panics
+/// If the file is not found.
+/// # Note
+/// The text will be printed in the following format:
+/// ```text
+/// #1 shortut1 shortut2 (14) afirstpath
+/// #2 short1 (12) asecondpath
+/// ```
+///
+pub fn rank(dpath: &str, limit: Option<usize>) -> ! {
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut data = binding.lines().filter_map(split_dline).collect::<Vec<_>>();
+    data.sort_by(|(_, _, fst), (_, _, snd)| snd.cmp(fst));
+    let mut ranks = String::new();
+    for (idx, (path, shorts, priory)) in data.iter().take(limit.unwrap_or(data.len())).enumerate() {
+        writeln!(
+            ranks,
+            "#{} {} ({priory}) {}",
+            idx.saturating_add(1),
+            shorts.join(" "),
+            unescape_path(path)
+        )
+        .write_error("lines");
+    }
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{ranks}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to print the most used directories.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `limit` - The number of directories to print (all of them if there are less)
+/// # Returns
+/// `None`.
+/// # Note
+/// The directories are sorted by priority, and printed in the following format:
+/// ```text
+/// 14  afirstpath   shortut1 shortut2
+///  9  asecondpath  short1
+/// ```
+pub fn top(dpath: &str, limit: usize) -> ! {
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut data = binding.lines().filter_map(split_dline).collect::<Vec<_>>();
+    data.sort_by(|(_, _, fst), (_, _, snd)| snd.cmp(fst));
+    data.truncate(limit);
+    let width = data
+        .first()
+        .map(|(_, _, priory)| priory.to_string().len())
+        .unwrap_or_default();
+    let paths = data
+        .iter()
+        .map(|(path, _, _)| unescape_path(path))
+        .collect::<Vec<_>>();
+    let path_width = paths
+        .iter()
+        .map(|path| path.chars().count())
+        .max()
+        .unwrap_or_default();
+    let mut tops = String::new();
+    for ((_, shorts, priory), path) in data.iter().zip(&paths) {
+        writeln!(
+            tops,
+            "{priory:>width$}  {path:<path_width$}  {}",
+            shorts.join(" ")
+        )
+        .write_error("lines");
+    }
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{tops}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to print the most recently used directories.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `hpath` - The path of the history file
+/// * `limit` - The number of directories to print (all of them if there are less)
+/// # Returns
+/// `None`.
+/// # Note
+/// The time a directory was last used is its last access marker (see `Marks`), or else the last time it was pushed in the history file.
+/// The directories never used are not printed. The others are sorted from the most recent one, and printed in the following format:
+/// ```text
+/// 5 min ago  afirstpath   shortut1 shortut2
+///  2 h ago   asecondpath  short1
 /// ```
-/// # Panics
-/// If the file is not found
+pub fn recent(dpath: &str, hpath: &str, limit: usize) -> ! {
+    let now = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .internal_error("Time went backwards.", None)
+        .as_secs();
+    let hist = fs::read_to_string(hpath).read_error(hpath, None);
+    let mut visited: collections::HashMap<String, u64> = collections::HashMap::new();
+    for hline in hist.lines() {
+        if let [pth, _, secs, ..] = split_line(hline.trim()).as_slice() {
+            if let Ok(sec) = secs.parse::<u64>() {
+                let path = unescape_path(pth);
+                let last = visited.entry(std_path(&path).unwrap_or(path)).or_default();
+                *last = (*last).max(sec);
+            }
+        }
+    }
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut data = binding
+        .lines()
+        .filter_map(|dline| split_dline(dline).map(|parts| (dline, parts)))
+        .filter_map(|(dline, (pth, shorts, _))| {
+            let path = unescape_path(pth);
+            let access = pop_markers(&mut split_line(dline.trim())).last_access;
+            Some(access)
+                .filter(|secs| *secs != 0)
+                .or_else(|| {
+                    visited
+                        .get(&std_path(&path).unwrap_or_else(|_| path.clone()))
+                        .copied()
+                })
+                .map(|secs| (secs, path, shorts))
+        })
+        .collect::<Vec<_>>();
+    data.sort_by(|(fst, ..), (snd, ..)| snd.cmp(fst));
+    data.truncate(limit);
+    let ages = data
+        .iter()
+        .map(|(secs, ..)| hist::age(now.saturating_sub(*secs)))
+        .collect::<Vec<_>>();
+    let width = ages
+        .iter()
+        .map(|ago| ago.chars().count())
+        .max()
+        .unwrap_or_default();
+    let path_width = data
+        .iter()
+        .map(|(_, path, _)| path.chars().count())
+        .max()
+        .unwrap_or_default();
+    let mut recents = String::new();
+    for ((_, path, shorts), ago) in data.iter().zip(&ages) {
+        writeln!(
+            recents,
+            "{ago:>width$}  {path:<path_width$}  {}",
+            shorts.join(" ")
+        )
+        .write_error("lines");
+    }
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{recents}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to clean the directory file in one go.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `dry_run` - `true` to only print what would be changed, without writing the directory file
+/// # Returns
+/// `None`.
+/// # Note
+/// The steps are run in the following order, and the number of lines changed by each step is printed:
+/// - the paths are normalized (see `std_path`),
+/// - the lines with the same path are merged (the shortcuts are joined, and the highest priority is kept),
+/// - the lines whose directory doesn't exist are removed,
+/// - the blank lines are removed,
+/// - the priorities are compacted into a dense range (`0`, `1`, `2`, ...), keeping their order.
 ///
-pub fn read(dpath: &str, args: &[Cmd], incr: u32) -> Option<String> {
-    let mut sstate = SearchState::default();
-    let mut success = false;
+/// The malformed lines are kept untouched at the end of the file.
+pub fn sweep(dpath: &str, dry_run: bool) -> ! {
+    // Held until the file is written, as `process::exit` doesn't release it
+    let Ok(guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+    else {
+        #[expect(
+            clippy::exit,
+            reason = "the process ends with the status of the command"
+        )]
+        process::exit(1);
+    };
+    let binding = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut lines: Vec<(String, Vec<&str>, u32, Marks)> = vec![];
+    let mut invalid = vec![];
+    let mut normalized: usize = 0;
+    let mut merged: usize = 0;
+    let mut blank: usize = 0;
+    for dline in binding.lines() {
+        if dline.trim().is_empty() {
+            blank = blank.saturating_add(1);
+            continue;
+        }
+        let Some((path, shorts, priory)) = split_dline(dline) else {
+            invalid.push(dline.trim());
+            continue;
+        };
+        let marks = pop_markers(&mut split_line(dline.trim()));
+        let full_path = unescape_path(path);
+        let new_path = escape_path(&std_path(&full_path).unwrap_or(full_path));
+        if new_path != path {
+            normalized = normalized.saturating_add(1);
+        }
+        if let Some((_, old_shorts, old_priory, old_marks)) =
+            lines.iter_mut().find(|(pth, ..)| *pth == new_path)
+        {
+            merged = merged.saturating_add(1);
+            for short in shorts {
+                if !old_shorts.contains(&short) {
+                    old_shorts.push(short);
+                }
+            }
+            *old_priory = (*old_priory).max(priory);
+            old_marks.frozen |= marks.frozen;
+            old_marks.last_access = old_marks.last_access.max(marks.last_access);
+        } else {
+            lines.push((new_path, shorts, priory, marks));
+        }
+    }
 
-    let mut data: String = fs::read_to_string(dpath)
-        .read_error(dpath, None)
-        .split('\n')
-        .map(|dline| read_dline(dline.trim(), args, &mut success, incr, &mut sstate))
-        .collect();
+    let total = lines.len();
+    lines.retain(|(pth, ..)| path::Path::new(&unescape_path(pth)).exists());
+    let missing = total.saturating_sub(lines.len());
 
-    // dbg!(&sstate);
+    let mut priories = lines
+        .iter()
+        .map(|(_, _, priory, _)| *priory)
+        .collect::<Vec<_>>();
+    priories.sort_unstable();
+    priories.dedup();
+    let mut compacted: usize = 0;
+    let mut data = String::new();
+    for (pth, shorts, priory, marks) in &lines {
+        let dense = priories
+            .iter()
+            .position(|pr| pr == priory)
+            .and_then(|idx| u32::try_from(idx).ok())
+            .internal_error("Priority not found after compaction", Some(*priory));
+        if dense != *priory {
+            compacted = compacted.saturating_add(1);
+        }
+        let end = Marks {
+            broken: false,
+            ..*marks
+        }
+        .end();
+        writeln!(data, "{pth};{};{dense}{end}", shorts.join(";")).write_error("lines");
+    }
+    for dline in invalid {
+        writeln!(data, "{dline}").write_error("lines");
+    }
 
-    let mut res = match sstate.correct {
-        Some(x) => Some(x),
-        None => sstate.prioritised,
+    if !dry_run {
+        write_dirs(dpath, &data, None);
     }
-    .map(|x| {
-        let mut result = x;
-        result.push('/');
-        result
-    });
-    let mut some = false;
-    let mut here = None;
+    drop(guard);
 
-    for arg in args {
-        match arg {
-            // Cmd::Get(ShortPath {
-            //     short: Some(short), ..
-            // }) if !success => {
-            //     some = false;
-            //     res = None;
-            // }
-            Cmd::Get(ShortPath { short, path }) => {
-                some = true;
-                if here.is_none() {
-                    here = (*short).clone();
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("Normalized paths: {normalized}");
+        println!("Merged duplicate paths: {merged}");
+        println!("Removed missing directories: {missing}");
+        println!("Removed blank lines: {blank}");
+        println!("Compacted priorities: {compacted}");
+        if dry_run {
+            println!("Dry run: {dpath} was not modified");
+        }
+    }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to check the directory file for problems.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `fix` - `true` to also repair the problems that can be repaired safely (with `--fix`)
+/// # Returns
+/// `None`.
+/// # Note
+/// The malformed lines (see `check_dline`), the shortcuts used more than once, the paths listed more than once and the directories that don't exist anymore are printed with the number and the content of their line, e.g.:
+/// ```text
+/// Line 4: shortcut u already used on line 1: /var;u;v;10
+/// ```
+/// With `--fix`, the malformed lines are removed, and the shortcuts already used by a previous line are removed from the next ones, unless it would leave a line without shortcut.
+/// The other problems are only printed: use `-sweep` to merge the duplicate paths, and `-prune` to remove the missing directories.
+pub fn doctor(dpath: &str, fix: bool) -> ! {
+    let mut problems = String::new();
+    let mut count: usize = 0;
+    let mut fixed: usize = 0;
+    {
+        let Ok(_guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+        else {
+            #[expect(
+                clippy::exit,
+                reason = "the process ends with the status of the command"
+            )]
+            process::exit(1);
+        };
+        let binding = fs::read_to_string(dpath).read_error(dpath, None);
+        let mut data = String::new();
+        let mut shorts = collections::HashMap::new();
+        let mut paths = collections::HashMap::new();
+        for (idx, dline) in binding.lines().enumerate() {
+            let line_nb = idx.saturating_add(1);
+            let mut report = |problem: &str| {
+                count = count.saturating_add(1);
+                writeln!(problems, "Line {line_nb}: {problem}: {}", dline.trim())
+                    .write_error("lines");
+            };
+            if let Err(er) = check_dline(dline) {
+                report(&er);
+                fixed = fixed.saturating_add(1);
+                continue;
+            }
+            let Some((pth, line_shorts, priory)) = split_dline(dline) else {
+                writeln!(data, "{dline}").write_error("lines");
+                continue;
+            };
+            let full_path = unescape_path(pth);
+            let std_full_path = std_path(&full_path).unwrap_or_else(|_| full_path.clone());
+            if let Some(first) = paths.get(&std_full_path) {
+                report(&format!("path already listed on line {first}"));
+            } else {
+                paths.insert(std_full_path, line_nb);
+            }
+            if !path::Path::new(&full_path).exists() {
+                report("directory doesn't exist anymore");
+            }
+            let mut kept = vec![];
+            for short in &line_shorts {
+                if let Some(first) = shorts.get(short) {
+                    report(&format!("shortcut {short} already used on line {first}"));
                 } else {
-                    user_error!("Multiple <-get> commands.");
-                }
-                if success {
-                    res = res
-                        .map(|content| format!("{content}{}", (*path).clone().unwrap_or_default()));
+                    shorts.insert(*short, line_nb);
+                    kept.push(*short);
                 }
             }
-            _ if success => (),
-            Cmd::Reset | Cmd::Decr(_) => (),
-            Cmd::Add(ShortPath { path: None, .. }) => user_error!("Missing path to <-add>"),
-            Cmd::Edit(ShortPath { path: None, .. }) => {
-                user_error!("Shortcut not found, and missing path to <-edit>");
+            if kept.is_empty() || kept.len() == line_shorts.len() {
+                writeln!(data, "{dline}").write_error("lines");
+            } else {
+                fixed = fixed.saturating_add(1);
+                let end = pop_markers(&mut split_line(dline.trim())).end();
+                writeln!(data, "{pth};{};{priory}{end}", kept.join(";")).write_error("lines");
             }
-            Cmd::Add(ShortPath {
-                short: opt_short,
-                path: Some(path),
-            })
-            | Cmd::Edit(ShortPath {
-                short: opt_short,
-                path: Some(path),
-            }) => match opt_short.as_ref() {
-                Some(short) => write!(data, "{};{};0", &path, short).write_error("Lines"),
-                None => user_error!("Missing shortut to add"),
+        }
+
+        if fix && fixed > 0 {
+            write_dirs(dpath, &data, None);
+        }
+    }
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{problems}");
+        if count == 0 {
+            println!("No problem found in {dpath}");
+        } else {
+            println!("Problems found in {dpath}: {count}");
+            match (fix, fixed) {
+                (true, _) => println!("Fixed lines: {fixed}"),
+                (false, 0) => (),
+                (false, _) => println!("Lines that can be fixed with --fix: {fixed}"),
+            }
+        }
+    }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to parse a line printed by `zoxide query --list --score`.
+/// # Arguments
+/// * `line` - The line, e.g. `  12.5 /home/user/folder`
+/// # Returns
+/// The score rounded to a priority, and the path, or `None` if the line is malformed.
+fn parse_zoxide(line: &str) -> Option<(u32, &str)> {
+    match line.trim().split_once(' ') {
+        Some((score, zpath)) => score.parse::<f64>().ok().map(|score_float| {
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                clippy::as_conversions,
+                reason = "the scores of zoxide are small positive numbers"
+            )]
+            let priory = score_float.round() as u32;
+            (priory, zpath.trim())
+        }),
+        None => None,
+    }
+}
+
+/// Function to import the directories of a zoxide database.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `db` - The folder of the zoxide database (`_ZO_DATA_DIR`) or the database file, if not the default one
+/// # Returns
+/// `None`.
+/// # Note
+/// The directories are listed with `zoxide query --list --score`, so zoxide must be installed.
+/// Each directory is added with its name as shortcut (see `path2dir`), and its score (rounded) as priority.
+/// The directories that don't exist anymore, that are already in the directory file, or whose name is already a shortcut, are skipped.
+/// The number of imported and skipped directories is printed.
+pub fn import_zoxide(dpath: &str, db: Option<&str>) -> ! {
+    let mut command = process::Command::new("zoxide");
+    command.args(["query", "--list", "--score"]);
+    if let Some(db_path) = db {
+        let db_folder = path::Path::new(db_path);
+        command.env(
+            "_ZO_DATA_DIR",
+            if db_folder.is_file() {
+                db_folder.parent().unwrap_or(db_folder)
+            } else {
+                db_folder
             },
+        );
+    }
+    let listed = match command.output() {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        Ok(output) => {
+            command_error!(
+                "zoxide failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            String::new()
+        }
+        Err(er) => {
+            command_error!("Unable to run zoxide: {er}");
+            String::new()
+        }
+    };
 
-            Cmd::Rm(_) => user_error!("Failed to remove shortcut: not found"),
-            Cmd::Del(_) => user_error!("Failed to delete path: not found"),
+    // Held until the file is written, as `process::exit` doesn't release it
+    let Ok(guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+    else {
+        #[expect(
+            clippy::exit,
+            reason = "the process ends with the status of the command"
+        )]
+        process::exit(1);
+    };
+    let mut data = fs::read_to_string(dpath).read_error(dpath, None);
+    let binding = data.clone();
+    let mut paths = collections::HashSet::new();
+    let mut shorts = collections::HashSet::new();
+    for (pth, line_shorts, _) in binding.lines().filter_map(split_dline) {
+        paths.insert(pth.to_owned());
+        shorts.extend(line_shorts.into_iter().map(str::to_owned));
+    }
+    if !data.is_empty() && !data.ends_with('\n') {
+        data.push('\n');
+    }
+
+    let mut imported: usize = 0;
+    let mut skipped: usize = 0;
+    for (priory, zpath) in listed.lines().filter_map(parse_zoxide) {
+        // A relative path can't be read without the current directory
+        let Ok(new_path) = std_path(zpath) else {
+            skipped = skipped.saturating_add(1);
+            continue;
         };
+        let short = path2dir(&new_path);
+        let stored = escape_path(&new_path);
+        if !path::Path::new(&new_path).exists()
+            || paths.contains(&stored)
+            || shorts.contains(&short)
+            || short.is_empty()
+            || short.contains(FORBIDDEN)
+        {
+            skipped = skipped.saturating_add(1);
+            continue;
+        }
+        writeln!(data, "{stored};{short};{priory}").write_error("lines");
+        paths.insert(stored);
+        shorts.insert(short);
+        imported = imported.saturating_add(1);
     }
+    write_dirs(dpath, &data, None);
+    drop(guard);
 
-    fs::write(dpath, data).write_error(dpath);
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("Imported directories: {imported}");
+        println!("Skipped directories: {skipped}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-    // dbg!(&some, &here, &res);
-    if some {
-        match here {
-            Some(local)
-                if res.is_none() || (!local.is_empty() && path::Path::new(&local).exists()) =>
-            {
-                Some(local)
-            }
-            _ => res,
+/// Function to export the directory file as JSON.
+/// # Arguments
+/// * `dpath` - The path of the directory file
+/// * `out` - The path of the JSON file to write, or `None` to print the JSON
+/// # Returns
+/// `None`.
+/// # Note
+/// The JSON is an object with the version of its format (see `JSON_VERSION`), and the array of the directories:
+/// ```json
+/// {
+///   "version": 2,
+///   "dirs": [
+///     {"path": "/home/user/folder", "shortcuts": ["f", "folder"], "priority": 14, "frozen": false}
+///   ]
+/// }
+/// ```
+/// The paths are exported unescaped, and the malformed lines are skipped. The matching `-import-json` reads it back.
+pub fn export_json(dpath: &str, out: Option<&str>) -> ! {
+    let content = fs::read_to_string(dpath).read_error(dpath, None);
+    let entries = content
+        .lines()
+        .filter_map(|dline| {
+            split_dline(dline).map(|(pth, line_shorts, priory)| {
+                let frozen = pop_markers(&mut split_line(dline.trim())).frozen;
+                let shorts = line_shorts
+                    .iter()
+                    .map(|short| json::escape(short))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "    {{\"path\": {}, \"shortcuts\": [{shorts}], \"priority\": {priory}, \"frozen\": {frozen}}}",
+                    json::escape(&unescape_path(pth))
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    let json = format!(
+        "{{\n  \"version\": {JSON_VERSION},\n  \"dirs\": [\n{}\n  ]\n}}\n",
+        entries.join(",\n")
+    );
+
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    match out {
+        Some(json_path) => {
+            write_atomic(json_path, &json).write_error(json_path);
+            println!("Exported directories: {}", entries.len());
         }
-    } else {
-        None
+        None => print!("{json}"),
+    }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Function to get the directories of the JSON of `-export-json`, checking the version of its format.
+/// # Arguments
+/// * `parsed` - The JSON read from the file, or the reason why it is invalid
+/// * `json_path` - The path of the JSON file, for the errors
+/// # Returns
+/// The JSON objects of the directories with the version of their format, or an empty slice if the JSON is invalid, or its version is missing or newer than `JSON_VERSION`.
+fn json_dirs<'json>(parsed: &'json Result<Json, String>, json_path: &str) -> (&'json [Json], u32) {
+    let json = match parsed {
+        Ok(json) => json,
+        Err(er) => {
+            data_error!("Invalid JSON in {json_path}: {er}");
+            return (&[], JSON_VERSION);
+        }
+    };
+    match json.get("version").and_then(Json::as_u32) {
+        Some(version) if version > JSON_VERSION => {
+            user_error!("{json_path} was exported by a newer version of goto (format {version}, this one reads up to {JSON_VERSION}): please upgrade goto");
+            (&[], version)
+        }
+        Some(version) => (
+            json.get("dirs")
+                .and_then(Json::as_array)
+                .data_error(format!("Missing array \"dirs\" in {json_path}"), None),
+            version,
+        ),
+        None => {
+            data_error!("Missing version in {json_path}: it wasn't written by <-export-json>");
+            (&[], JSON_VERSION)
+        }
+    }
+}
+
+/// Function to get a field of a directory of the JSON of `-export-json`, under its current name or under the name it had in the version of the JSON.
+/// # Arguments
+/// * `entry` - The JSON object of the directory
+/// * `name` - The current name of the field
+/// * `version` - The version of the format of the JSON
+/// # Returns
+/// The value of the field, or `None` if it is missing.
+/// # Example
+/// ```ignore
+/// let entry = json::parse(r#"{"names": ["f"]}"#).unwrap();
+/// assert!(json_field(&entry, "shortcuts", 1).is_some());
+/// assert!(json_field(&entry, "shortcuts", 2).is_none());
+/// ```
+fn json_field<'json>(entry: &'json Json, name: &str, version: u32) -> Option<&'json Json> {
+    entry.get(name).or_else(|| {
+        JSON_RENAMES
+            .iter()
+            .find(|(since, new_name, _)| version < *since && *new_name == name)
+            .and_then(|(_, _, old_name)| entry.get(old_name))
+    })
+}
+
+/// Function to read a directory of the JSON of `-export-json`.
+/// # Arguments
+/// * `entry` - The JSON object of the directory
+/// * `version` - The version of the format of the JSON, to read the fields that were renamed since (see `json_field`)
+/// # Returns
+/// The path, the shortcuts, the priority, and `true` if the priority is frozen (`false` if not given).
+/// # Errors
+/// The reason why the object is malformed.
+fn json_entry(entry: &Json, version: u32) -> Result<(String, Vec<String>, u32, bool), String> {
+    let Some(json_path) = json_field(entry, "path", version).and_then(Json::as_str) else {
+        return Err(String::from("missing or invalid \"path\""));
+    };
+    let Some(shorts) = json_field(entry, "shortcuts", version)
+        .and_then(Json::as_array)
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_str().map(str::to_owned))
+                .collect::<Option<Vec<_>>>()
+        })
+    else {
+        return Err(String::from("missing or invalid \"shortcuts\""));
+    };
+    if shorts.is_empty()
+        || shorts
+            .iter()
+            .any(|short| short.is_empty() || short.contains(FORBIDDEN))
+    {
+        return Err(String::from(
+            "no shortcut, or an empty shortcut, or a shortcut containing <;>, </>, <\\> or a newline",
+        ));
     }
+    let Some(priory) = json_field(entry, "priority", version).and_then(Json::as_u32) else {
+        return Err(String::from("missing or invalid \"priority\""));
+    };
+    let frozen = json_field(entry, "frozen", version)
+        .and_then(Json::as_bool)
+        .unwrap_or(false);
+    Ok((json_path.to_owned(), shorts, priory, frozen))
+}
+
+/// Function to merge a directory of the JSON of `-export-json` into its line of the directory file (see `import_json`).
+/// # Arguments
+/// * `dline` - The line of the directory file
+/// * `new_shorts` - The shortcuts of the directory that don't exist yet
+/// * `priory` - The priority of the directory in the JSON
+/// # Returns
+/// The line with the new shortcuts and the maximum of the two priorities, or `None` if the line is malformed.
+fn merge_dline(dline: &str, new_shorts: &[String], priory: u32) -> Option<String> {
+    let marks = pop_markers(&mut split_line(dline.trim()));
+    split_dline(dline).map(|(pth, line_shorts, line_priory)| {
+        format!(
+            "{pth};{};{}{}",
+            line_shorts
+                .into_iter()
+                .chain(new_shorts.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(";"),
+            line_priory.max(priory),
+            marks.end()
+        )
+    })
 }
 
-/// Function to print state of the directories
+/// Function to import the JSON of `-export-json` into the directory file.
 /// # Arguments
 /// * `dpath` - The path of the directory file
+/// * `json_path` - The path of the JSON file
 /// # Returns
-/// `None`
-/// # Panics
-/// If the file is not found
+/// `None`.
 /// # Note
-/// The text will be printed in the following format:
-/// ```text
-/// afirstpath  shortut1      shortut2 14
-/// asecondpath short1         short2    14
-/// third       afirstshortut           14
-/// ```
-///
-pub fn state(dpath: &str) -> ! {
-    let mut spaces: Vec<usize> = vec![];
-    let binding = fs::read_to_string(dpath).read_error(dpath, None);
-    let data = binding.lines().collect::<Vec<&str>>();
-    for dline in &data {
-        dline.split(';').enumerate().for_each(|(idx, elt)| {
-            let new = elt.len().checked_add(1).unwrap_or(elt.len());
-            match spaces.get_mut(idx) {
-                Some(space) if new > *space => {
-                    *space = new;
+/// If the path of a directory is already in the directory file, its shortcuts are added to the line, and the maximum of the two priorities is kept.
+/// Otherwise, a new line is added at the end of the directory file. The paths are normalized (see `std_path`).
+/// The shortcuts that already exist are not added (the directory is skipped if none is left), and the malformed directories are skipped with an error.
+/// The number of added, merged and skipped directories is printed.
+pub fn import_json(dpath: &str, json_path: &str) -> ! {
+    let text = fs::read_to_string(json_path).read_error(json_path, None);
+    let parsed = json::parse(&text);
+    let (entries, version) = json_dirs(&parsed, json_path);
+    // Held until the file is written, as `process::exit` doesn't release it
+    let Ok(guard) = lock(dpath).inspect_err(|er| file_error!("Unable to lock {dpath}: {er}"))
+    else {
+        #[expect(
+            clippy::exit,
+            reason = "the process ends with the status of the command"
+        )]
+        process::exit(1);
+    };
+    let content = fs::read_to_string(dpath).read_error(dpath, None);
+    let mut lines = content.lines().map(str::to_owned).collect::<Vec<_>>();
+    let mut existing = lines
+        .iter()
+        .filter_map(|dline| split_dline(dline))
+        .flat_map(|(_, line_shorts, _)| line_shorts)
+        .map(str::to_owned)
+        .collect::<collections::HashSet<_>>();
+
+    let mut added: usize = 0;
+    let mut merged: usize = 0;
+    let mut skipped: usize = 0;
+    for (idx, entry) in entries.iter().enumerate() {
+        // The path is stored normalized and escaped, like the ones of `-add`
+        let normalized =
+            json_entry(entry, version).and_then(|(new_path, shorts, priory, frozen)| {
+                std_path(&new_path)
+                    .map(|std_new_path| {
+                        (escape_path(&std_new_path), new_path, shorts, priory, frozen)
+                    })
+                    .map_err(|er| er.to_string())
+            });
+        let (stored, new_path, shorts, priory, frozen) = match normalized {
+            Ok(fields) => fields,
+            Err(er) => {
+                data_error!(
+                    "Skipped the directory number {} of {json_path}: {er}",
+                    idx.saturating_add(1)
+                );
+                skipped = skipped.saturating_add(1);
+                continue;
+            }
+        };
+        let mut new_shorts: Vec<String> = vec![];
+        for short in shorts {
+            if !existing.contains(&short) && !new_shorts.contains(&short) {
+                new_shorts.push(short);
+            }
+        }
+        match lines
+            .iter_mut()
+            .find(|dline| split_dline(dline).is_some_and(|(pth, ..)| pth == stored))
+        {
+            Some(dline) => {
+                if let Some(line) = merge_dline(dline, &new_shorts, priory) {
+                    *dline = line;
                 }
-                Some(_) => (),
-                None => spaces.push(new),
-            };
-        });
-    }
-    spaces.pop();
-    let total_space = spaces.iter().sum::<usize>();
-    let mut state = String::new();
-    for dline in &data {
-        let mut sline = dline.split(';').collect::<Vec<&str>>();
-        match sline.pop() {
-            None => continue,
-            Some(priory) => {
-                let mut str1 = String::new();
-                sline.iter().enumerate().for_each(|(idx, elt)| {
-                    let space = spaces.get(idx).unwrap_or(&0);
-                    write!(&mut str1, "{elt:<space$}").write_error("line");
-                });
-                writeln!(state, "{str1:<total_space$}{priory}").write_error("lines");
+                merged = merged.saturating_add(1);
+            }
+            None if new_shorts.is_empty() => {
+                data_error!("Skipped {new_path}: all its shortcuts already exist");
+                skipped = skipped.saturating_add(1);
+            }
+            None => {
+                let marks = Marks {
+                    frozen,
+                    ..Marks::default()
+                };
+                lines.push(format!(
+                    "{stored};{};{priory}{}",
+                    new_shorts.join(";"),
+                    marks.end()
+                ));
+                added = added.saturating_add(1);
             }
         }
+        existing.extend(new_shorts);
     }
+    let mut data = lines.join("\n");
+    if !data.is_empty() {
+        data.push('\n');
+    }
+    write_dirs(dpath, &data, None);
+    drop(guard);
 
-    #[allow(clippy::print_stdout)]
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
     {
-        print!("{state}");
+        println!("Added directories: {added}");
+        println!("Merged directories: {merged}");
+        println!("Skipped directories: {skipped}");
     };
-    #[allow(clippy::exit)]
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
     process::exit(0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// Function to read a line with the first command of `args`, as `read` does for each line.
+    fn read_line(rdline: &str, args: &[&str]) -> (String, bool) {
+        let gdata = GlobalData {
+            incr: 10,
+            tiers: vec![Tier::Exact],
+            ..GlobalData::default()
+        };
+        let values = args
+            .iter()
+            .map(|arg| String::from(*arg))
+            .collect::<Vec<_>>();
+        let (cmds, _, _) = crate::parse_args(&gdata, &values).unwrap_or_default();
+        let mut success = false;
+        let line = read_dline(
+            rdline,
+            &cmds,
+            &mut success,
+            &gdata,
+            &mut SearchState::default(),
+        );
+        (line, success)
+    }
+
+    /// Function to get the temporary folder, formatted as the paths of the directory file.
+    fn temp_dir() -> String {
+        std_path(&env::temp_dir().to_string_lossy()).unwrap_or_default()
+    }
+
+    /// Function to write a directory file in the temporary folder, unique to the test.
+    fn temp_dirs(name: &str, content: &str) -> String {
+        let dpath = env::temp_dir()
+            .join(format!("goto-{}-{name}.csv", process::id()))
+            .to_string_lossy()
+            .into_owned();
+        fs::write(&dpath, content).write_error(&dpath);
+        dpath
+    }
+
+    /// Function to run a command on a directory file, as `main` does.
+    fn run(dpath: &str, args: &[&str]) -> Option<String> {
+        let values = args
+            .iter()
+            .map(|arg| String::from(*arg))
+            .collect::<Vec<_>>();
+        let gdata = GlobalData::default();
+        // `main` stops when the arguments are invalid, before reading the file
+        let Ok((cmds, _, _)) = crate::parse_args(&gdata, &values) else {
+            return None;
+        };
+        read(dpath, &cmds, &gdata, false, false)
+    }
+
+    /// Function to read a directory file, and remove it with the files written next to it.
+    fn take_dirs(dpath: &str) -> Vec<u8> {
+        let content = fs::read(dpath).unwrap_or_default();
+        for file in [dpath, &format!("{dpath}.undo"), &format!("{dpath}.lock")] {
+            drop(fs::remove_file(file));
+        }
+        content
+    }
+
+    #[test]
+    fn add_from_several_threads_keeps_every_shortcut() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs("add-threads", "");
+        let shorts = ["t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7"];
+        let threads = shorts.map(|short| {
+            let (thread_dpath, thread_tmp) = (dpath.clone(), tmp.clone());
+            thread::spawn(move || run(&thread_dpath, &["-add", short, &thread_tmp]))
+        });
+        for handle in threads {
+            assert!(handle.join().ok().is_some());
+        }
+        let content = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        for short in shorts {
+            assert!(content.contains(&format!(";{short};")));
+        }
+    }
+
+    #[test]
+    fn add_invalid_shortcut_keeps_the_file() {
+        let tmp = temp_dir();
+        let content = format!("{tmp};t;5;@100\n");
+        let dpath = temp_dirs("add-semicolon", &content);
+        assert_eq!(run(&dpath, &["-add", "a;b", &tmp]), None);
+        assert_eq!(take_dirs(&dpath), content.as_bytes());
+    }
+
+    #[test]
+    fn add_shortcut_ending_with_backslash_keeps_the_file() {
+        let tmp = temp_dir();
+        let content = format!("{tmp};t;5\n");
+        let dpath = temp_dirs("add-backslash", &content);
+        assert_eq!(run(&dpath, &["-add", "a\\", &tmp]), None);
+        assert_eq!(take_dirs(&dpath), content.as_bytes());
+    }
+
+    #[test]
+    fn add_creates_the_missing_folder_of_the_file() {
+        let tmp = temp_dir();
+        let folder = env::temp_dir().join(format!("goto-{}-fresh", process::id()));
+        let dpath = folder.join("dirs.csv").to_string_lossy().into_owned();
+        run(&dpath, &["-add", "t", &tmp]);
+        let content = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        drop(fs::remove_dir(folder));
+        assert!(content.starts_with(&format!("{tmp};t;")));
+    }
+
+    #[test]
+    fn add_reserved_shortcut_keeps_the_file() {
+        let tmp = temp_dir();
+        let content = format!("{tmp};t;5\n");
+        let dpath = temp_dirs("add-dash", &content);
+        assert_eq!(run(&dpath, &["-add", "-", "code", "/x"]), None);
+        assert_eq!(take_dirs(&dpath), content.as_bytes());
+    }
+
+    #[test]
+    fn add_keeps_a_path_with_spaces() {
+        let dir = env::temp_dir().join(format!("goto-{}-My Projects", process::id()));
+        fs::create_dir_all(&dir).write_error(&dir.to_string_lossy());
+        let path = std_path(&dir.to_string_lossy()).unwrap_or_default();
+        let dpath = temp_dirs("add-spaces", "");
+        run(&dpath, &["-add", "proj", &path]);
+        assert_eq!(run(&dpath, &["proj"]), Some(format!("{path}/")));
+        let content = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        assert!(content.starts_with(&format!("{path};proj;")));
+        drop(fs::remove_dir(dir));
+    }
+
+    #[test]
+    fn add_keeps_a_path_with_the_separator() {
+        let dir = env::temp_dir().join(format!("goto-{}-c#proj", process::id()));
+        fs::create_dir_all(&dir).write_error(&dir.to_string_lossy());
+        let path = std_path(&dir.to_string_lossy()).unwrap_or_default();
+        let dpath = temp_dirs("add-separator", "");
+        run(&dpath, &["-add", "csharp", &path]);
+        assert_eq!(run(&dpath, &["-get", "csharp"]), Some(format!("{path}/")));
+        let output = format!("0#1#{path}/");
+        // The wrappers read everything after the second separator (see `release/g`)
+        assert_eq!(
+            output.splitn(3, '#').last(),
+            Some(format!("{path}/").as_str())
+        );
+        drop(take_dirs(&dpath));
+        drop(fs::remove_dir(dir));
+    }
+
+    #[test]
+    fn add_keeps_each_shortcut_once() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs("add-repeated", "");
+        run(&dpath, &["-add", "x", "x", &tmp]);
+        run(&dpath, &["-add", "y", "y", &tmp]);
+        let content = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        assert!(content.starts_with(&format!("{tmp};x;y;")));
+        assert_eq!(content.lines().count(), 1);
+    }
+
+    #[test]
+    fn add_merges_into_the_line_of_the_path() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs("add-merge", "");
+        run(&dpath, &["-add", "a", &tmp]);
+        run(&dpath, &["-add", "b", &tmp]);
+        let content = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        assert!(content.starts_with(&format!("{tmp};a;b;")));
+        assert_eq!(content.lines().count(), 1);
+    }
+
+    #[test]
+    fn clear_saves_the_file_for_undo() {
+        let content = "/home/user/folder;f;5\n";
+        let dpath = temp_dirs("clear", content);
+        clear(&dpath);
+        let undo = fs::read_to_string(format!("{dpath}.undo")).unwrap_or_default();
+        assert_eq!(take_dirs(&dpath), b"");
+        assert_eq!(undo, content);
+    }
+
+    #[test]
+    fn delete_removes_the_line_of_a_shortcut() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs(
+            "delete",
+            &format!("/home/user/folder;f;folder;5\n{tmp};t;tmp;3\n"),
+        );
+        run(&dpath, &["-delete", "f"]);
+        assert_eq!(take_dirs(&dpath), format!("{tmp};t;tmp;3\n").as_bytes());
+    }
+
+    #[test]
+    fn edit_keeps_the_markers() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs("edit-frozen", "/home/user/folder;f;5;@100;!frozen\n");
+        run(&dpath, &["-edit", "f", &tmp]);
+        assert_eq!(
+            take_dirs(&dpath),
+            format!("{tmp};f;5;@100;!frozen\n").as_bytes()
+        );
+    }
+
+    #[test]
+    fn get_falls_back_to_a_fuzzy_match() {
+        let tmp = temp_dir();
+        let dpath = temp_dirs(
+            "fuzzy",
+            &format!("{tmp};documents;1\n/home/user/music;music;50\n"),
+        );
+        assert_eq!(run(&dpath, &["documnts"]), Some(format!("{tmp}/")));
+        assert_eq!(run(&dpath, &["zzzzz"]), None);
+        take_dirs(&dpath);
+        // `run` would go to the `docs` folder of the crate, where the tests run, before looking for a shortcut
+        let gdata = GlobalData::default();
+        let (cmds, _, _) = crate::parse_args(&gdata, &[String::from("docs")]).unwrap_or_default();
+        let mut sstate = SearchState::default();
+        read_dline(
+            &format!("{tmp};documents;1"),
+            &cmds,
+            &mut false,
+            &gdata,
+            &mut sstate,
+        );
+        assert_eq!(sstate.fuzzy, Some(tmp));
+    }
+
+    #[test]
+    fn json_dirs_rejects_a_newer_version() {
+        let parsed = json::parse(
+            r#"{"version": 3, "dirs": [{"path": "/x", "shortcuts": ["x"], "priority": 1}]}"#,
+        );
+        assert!(json_dirs(&parsed, "newer.json").0.is_empty());
+    }
+
+    #[test]
+    fn json_entry_reads_the_old_names_of_the_fields() {
+        let parsed = json::parse(
+            r#"{"version": 1, "dirs": [{"path": "/x", "names": ["x", "y"], "priority": 4}]}"#,
+        );
+        let (entries, version) = json_dirs(&parsed, "old.json");
+        assert_eq!(version, 1);
+        assert_eq!(
+            entries.first().map(|entry| json_entry(entry, version)),
+            Some(Ok((
+                String::from("/x"),
+                vec![String::from("x"), String::from("y")],
+                4,
+                false
+            )))
+        );
+        // `names` is only read in the versions that used it
+        assert!(entries
+            .first()
+            .is_some_and(|entry| json_entry(entry, JSON_VERSION).is_err()));
+    }
+
+    #[test]
+    fn read_dline_increments_the_shortcut() {
+        // The directory must exist, else it is marked as broken
+        let tmp = temp_dir();
+        assert_eq!(
+            read_line(&format!("{tmp};f;folder;1"), &["folder"]),
+            (format!("{tmp};f;folder;11\n"), true)
+        );
+        assert_eq!(
+            read_line(&format!("{tmp};f;folder;1"), &["g"]),
+            (format!("{tmp};f;folder;1\n"), false)
+        );
+    }
+
+    #[test]
+    fn read_dline_saturates_at_the_maximum_priority() {
+        let tmp = temp_dir();
+        let gdata = GlobalData {
+            incr: 10,
+            max_priority: 100,
+            tiers: vec![Tier::Exact],
+            ..GlobalData::default()
+        };
+        let (cmds, _, _) = crate::parse_args(&gdata, &[String::from("f")]).unwrap_or_default();
+        let mut success = false;
+        let line = read_dline(
+            &format!("{tmp};f;95"),
+            &cmds,
+            &mut success,
+            &gdata,
+            &mut SearchState::default(),
+        );
+        assert_eq!(line, format!("{tmp};f;100\n"));
+    }
+
+    #[test]
+    fn rescale_halves_the_priorities_at_the_maximum() {
+        assert_eq!(
+            rescale("/a;a;100\n/b;b;41;!frozen\n", 100).as_deref(),
+            Some("/a;a;50\n/b;b;20;!frozen\n")
+        );
+        assert_eq!(rescale("/a;a;99\n/b;b;41\n", 100), None);
+    }
+
+    #[test]
+    fn read_dline_decrements_a_numeric_shortcut() {
+        let tmp = temp_dir();
+        assert_eq!(
+            read_line(&format!("{tmp};0;20"), &["-decrement", "0", "5"]),
+            (format!("{tmp};0;15\n"), true)
+        );
+        assert_eq!(
+            read_line(&format!("{tmp};f;20"), &["-decrement", "f"]),
+            (format!("{tmp};f;20\n"), false)
+        );
+        assert_eq!(
+            read_line(&format!("{tmp};f;20"), &["-decrement", "5"]),
+            (format!("{tmp};f;15\n"), false)
+        );
+    }
+
+    #[test]
+    fn read_dline_keeps_the_markers() {
+        let (reset, _) = read_line("/home/user/folder;f;5;@100;!frozen", &["-reset"]);
+        assert_eq!(reset, "/home/user/folder;f;0;@100;!frozen\n");
+        let (decr, _) = read_line("/home/user/folder;f;5;!broken", &["-decrement", "f", "2"]);
+        assert_eq!(decr, "/home/user/folder;f;3;!broken\n");
+    }
+
+    #[test]
+    fn read_dline_with_escaped_path() {
+        let (line, success) = read_line("/home/a\\;b;f;1", &["f"]);
+        assert!(success);
+        assert_eq!(line, "/home/a\\;b;f;11;!broken\n");
+    }
+
+    #[test]
+    fn read_dline_skips_empty_lines() {
+        assert_eq!(read_line("", &["f"]), (String::new(), false));
+    }
+
+    #[test]
+    fn state_aligns_the_priorities() {
+        let dpath = temp_dirs("state", "/home/user/a;a;5\n/home/user/bb;b1;b2;b3;b4;12\n");
+        let table = state(&dpath, false, false);
+        take_dirs(&dpath);
+        // The shortcuts of a line are a single column, whatever their number
+        let columns = table
+            .lines()
+            .map(|line| line.rfind(' '))
+            .collect::<Vec<_>>();
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns.first(), columns.last());
+        assert!(table.contains("/home/user/bb b1 b2 b3 b4 12"));
+    }
+
+    #[test]
+    fn write_replaces_a_stale_temporary_file() {
+        let tmp = temp_dir();
+        let content = format!("{tmp};t;5\n");
+        let dpath = temp_dirs("stale-tmp", &content);
+        // Left by a write interrupted before the rename (see `write_atomic`)
+        let stale = format!("{dpath}.tmp");
+        fs::write(&stale, "/home/user/trunc").write_error(&stale);
+        assert_eq!(fs::read_to_string(&dpath).ok(), Some(content));
+        assert_eq!(run(&dpath, &["t"]), Some(format!("{tmp}/")));
+        run(&dpath, &["-add", "u", &tmp]);
+        assert!(!path::Path::new(&stale).exists());
+        let written = String::from_utf8(take_dirs(&dpath)).unwrap_or_default();
+        assert!(written.starts_with(&format!("{tmp};t;u;")));
+    }
+}
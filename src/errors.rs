@@ -1,27 +1,33 @@
-#![allow(dead_code)]
+#![allow(dead_code, reason = "some of the errors are only used by the binary")]
 
 use core::fmt;
+use std::env;
+use std::io::{self, IsTerminal as _};
 
 /// Macro to print general errors.
 /// # Examples
 /// ```
-/// general_error!("Shortcut {shortc} already exists");
+/// use goto::general_error;
+/// let shortc = "f";
+/// general_error!("User Error", "Shortcut {shortc} already exists");
 /// ```
 #[macro_export]
 macro_rules! general_error {
     ($type:expr, $($arg:tt)*) => {
-        eprintln!("\x1b[31m[{}] {}:{}:{}.\n{}.\x1b[0m", $type, file!(), line!(), column!(), format!($($arg)*))
+        eprintln!("{}", $crate::errors::color(&format!("[{}] {}:{}:{}.\n{}.", $type, file!(), line!(), column!(), format!($($arg)*))))
     };
 }
 
 /// Macro to print user error.
 /// # Examples
 /// ```
+/// use goto::{general_error, user_error};
+/// let shortc = "f";
 /// user_error!("Shortcut {shortc} already exists");
 /// ```
 /// Output:
 /// ```shell
-/// [User Error] Shortcut {shortc} already exists.
+/// [User Error] Shortcut f already exists.
 /// ```
 #[macro_export]
 macro_rules! user_error {
@@ -68,18 +74,19 @@ macro_rules! command_error {
   };
 }
 
-/// Trait to print error of opening a file (uses `eprintln!`)
+/// Trait to print error of opening a file (uses `eprintln!`).
 /// # Examples
-/// ```
+/// ```no_run
+/// use goto::errors::ReadError;
+/// use std::fs;
 /// let fpath = "file.txt";
-/// fs::read_to_string(fpath).file_error(fpath);
-///
+/// let content = fs::read_to_string(fpath).read_error(fpath, None);
+/// ```
 pub trait ReadError<T> {
     /// Unable to open a file.
     fn read_error(self, fpath: &str, default: Option<T>) -> T;
 }
 
-#[allow(clippy::print_stderr)]
 impl<T: Default, E: fmt::Display> ReadError<T> for Result<T, E> {
     fn read_error(self, fpath: &str, default: Option<T>) -> T {
         let def = default.unwrap_or_else(|| T::default());
@@ -93,18 +100,19 @@ impl<T: Default, E: fmt::Display> ReadError<T> for Result<T, E> {
     }
 }
 
-/// Trait to print error of writing in a file (uses `eprintln!`)
+/// Trait to print error of writing in a file (uses `eprintln!`).
 /// # Examples
-/// ```
+/// ```no_run
+/// use goto::errors::WriteError;
+/// use std::fs;
 /// let fpath = "file.txt";
-/// fs::read_to_string(fpath).file_error(fpath);
-///
+/// fs::write(fpath, "content").write_error(fpath);
+/// ```
 pub trait WriteError<E> {
     /// Unable to write a file.
     fn write_error(self, fpath: &str);
 }
 
-#[allow(clippy::print_stderr)]
 impl<E: fmt::Display> WriteError<E> for Result<(), E> {
     fn write_error(self, fpath: &str) {
         self.unwrap_or_else(|er| file_error!("Unable to write in file {fpath}: {er}"));
@@ -113,71 +121,59 @@ impl<E: fmt::Display> WriteError<E> for Result<(), E> {
 
 /// Trait to print internal errors (uses `panic!`).
 /// These errors are logic erros. When they occur, please contact the developer.
-#[allow(dead_code)]
 pub trait SingleError<T, U> {
-    /// Print an internal error.
-    fn internal_error(self, msg: U, default: Option<T>) -> T;
     /// Print a data error.
     fn data_error(self, msg: U, default: Option<T>) -> T;
+    /// Print an internal error.
+    fn internal_error(self, msg: U, default: Option<T>) -> T;
     /// Print a user error.
     fn user_error(self, msg: U, default: Option<T>) -> T;
 }
 
-#[allow(clippy::print_stderr)]
 impl<T: Default, U: fmt::Display> SingleError<T, U> for Option<T> {
-    fn internal_error(self, msg: U, default: Self) -> T {
+    fn data_error(self, msg: U, default: Self) -> T {
         let def = default.unwrap_or_else(|| T::default());
-        self.map_or_else(
-            || {
-                internal_error!("{msg}");
-                def
-            },
-            |val| val,
-        )
+        self.unwrap_or_else(|| {
+            data_error!("{msg}");
+            def
+        })
     }
 
-    fn data_error(self, msg: U, default: Self) -> T {
+    fn internal_error(self, msg: U, default: Self) -> T {
         let def = default.unwrap_or_else(|| T::default());
-        self.map_or_else(
-            || {
-                data_error!("{msg}");
-                def
-            },
-            |val| val,
-        )
+        self.unwrap_or_else(|| {
+            internal_error!("{msg}");
+            def
+        })
     }
 
     fn user_error(self, msg: U, default: Self) -> T {
         let def = default.unwrap_or_else(|| T::default());
-        self.map_or_else(
-            || {
-                user_error!("{msg}");
-                def
-            },
-            |val| val,
-        )
+        self.unwrap_or_else(|| {
+            user_error!("{msg}");
+            def
+        })
     }
 }
 
-#[allow(clippy::print_stderr)]
 impl<T: Default, U: fmt::Display, E: fmt::Display> SingleError<T, U> for Result<T, E> {
-    fn internal_error(self, msg: U, default: Option<T>) -> T {
+    fn data_error(self, msg: U, default: Option<T>) -> T {
         let def = default.unwrap_or_else(|| T::default());
         match self {
             Ok(val) => val,
             Err(er) => {
-                internal_error!("{er}.\n{msg}");
+                data_error!("{er}.\n{msg}");
                 def
             }
         }
     }
 
-    fn data_error(self, msg: U, default: Option<T>) -> T {
+    fn internal_error(self, msg: U, default: Option<T>) -> T {
         let def = default.unwrap_or_else(|| T::default());
         match self {
             Ok(val) => val,
             Err(er) => {
-                data_error!("{er}.\n{msg}");
+                internal_error!("{er}.\n{msg}");
                 def
             }
         }
@@ -199,56 +195,68 @@ impl<T: Default, U: fmt::Display, E: fmt::Display> SingleError<T, U> for Result<
 pub trait InteractionError<T> {
     /// Print a command error.
     fn command_error(self, msg: &str) -> T;
-    /// Print a user error.
-    fn user_error(self, msg: &str) -> T;
     /// Print a system error.
     fn system_error(self, msg: &str) -> T;
+    /// Print a user error.
+    fn user_error(self, msg: &str) -> T;
 }
 
 impl<T: Default, E: fmt::Display> InteractionError<T> for Result<T, E> {
-    #[allow(clippy::print_stderr)]
     fn command_error(self, msg: &str) -> T {
         self.unwrap_or_else(|er| {
             command_error!("{msg}.\n{er}");
             T::default()
         })
     }
-    #[allow(clippy::print_stderr)]
-    fn user_error(self, msg: &str) -> T {
+    fn system_error(self, msg: &str) -> T {
         self.unwrap_or_else(|er| {
-            user_error!("{msg}.\n{er}");
+            system_error!("{msg}.\n{er}");
             T::default()
         })
     }
-    #[allow(clippy::print_stderr)]
-    fn system_error(self, msg: &str) -> T {
+    fn user_error(self, msg: &str) -> T {
         self.unwrap_or_else(|er| {
-            system_error!("{msg}.\n{er}");
+            user_error!("{msg}.\n{er}");
             T::default()
         })
     }
 }
 
 impl<T: Default> InteractionError<T> for Option<T> {
-    #[allow(clippy::print_stderr)]
     fn command_error(self, msg: &str) -> T {
         self.unwrap_or_else(|| {
             command_error!("{msg}");
             T::default()
         })
     }
-    #[allow(clippy::print_stderr)]
-    fn user_error(self, msg: &str) -> T {
+    fn system_error(self, msg: &str) -> T {
         self.unwrap_or_else(|| {
-            user_error!("{msg}");
+            system_error!("{msg}");
             T::default()
         })
     }
-    #[allow(clippy::print_stderr)]
-    fn system_error(self, msg: &str) -> T {
+    fn user_error(self, msg: &str) -> T {
         self.unwrap_or_else(|| {
-            system_error!("{msg}");
+            user_error!("{msg}");
             T::default()
         })
     }
 }
+
+/// Function to colour an error in red, if the colours are enabled.
+/// # Arguments
+/// * `error` - The error to colour
+/// # Returns
+/// The error, between the ANSI codes of the red colour if the colours are enabled.
+/// # Note
+/// The colours are enabled if stderr is a terminal. `NO_COLOR` disables them, and `CLICOLOR_FORCE` forces them (e.g. to pipe the errors to `less -R`).
+pub fn color(error: &str) -> String {
+    let set = |var: &str| env::var(var).is_ok_and(|value| !value.is_empty() && value != "0");
+    if env::var("NO_COLOR").is_ok_and(|value| !value.is_empty())
+        || !(set("CLICOLOR_FORCE") || io::stderr().is_terminal())
+    {
+        error.to_owned()
+    } else {
+        format!("\x1b[31m{error}\x1b[0m")
+    }
+}
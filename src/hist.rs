@@ -1,20 +1,34 @@
-use crate::commands::std_path;
-use crate::errors::{ReadError, SingleError, WriteError};
-use crate::{data_error, file_error, general_error};
-use std::io::{self, BufRead, BufWriter, Write};
+use crate::commands::{escape_path, lock, split_line, std_path, unescape_path, write_atomic};
+use crate::errors::{ReadError as _, SingleError as _, WriteError as _};
+use crate::{data_error, file_error, general_error, user_error, GlobalData};
+use core::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead as _};
 use std::path::Path;
 use std::process;
 use std::time;
-use std::{collections, fs};
 // Imports
 
+/// Error returned by `popd` when no directory can be popped.
+#[derive(Debug)]
+pub enum PopError {
+    /// The history file contains no directory.
+    EmptyHistory,
+    /// The history file can't be opened.
+    IoError(io::Error),
+    /// The history file has no directory that still exists, except the current one.
+    NoValidEntry,
+}
+
 /// Push a directory to the history file.
 /// # Arguments
-/// * `histpath` - The path to the history file
+/// * `gdata` - The global data, for the path to the history file (`hist`), the path to the file of the popped directories (`fwd`, see `popd`), emptied as the navigation starts a new branch, and the limits of the history (`hist_ttl` and `hist_max_lines`)
 /// * `path` - The path to the directory to push
 /// # Example
-/// ```
-/// pushd("lib/hist.csv", "/home/user/folder");
+/// ```no_run
+/// use goto::{hist, GlobalData};
+/// let gdata = GlobalData::default();
+/// hist::pushd(&gdata, "/home/user/folder");
 /// ```
 /// # Note
 /// The history file is a simple text file with the following format:
@@ -22,41 +36,84 @@ use std::{collections, fs};
 /// /home/user/folder1;pid1;time1
 /// /home/user/folder2;pid2;time2
 /// ```
-/// Where `pid` is the process id of the process that pushed the directory and `time` is the time in seconds since the Unix Epoch.
+/// Where `pid` is the id of the shell (`GOTO_SHELL_ID`, see `GlobalData::shell_id`), or the process id of the process that pushed the directory if it isn't set, and `time` is the time in seconds since the Unix Epoch.
 /// The last line is the most recent directory pushed.
+/// The semicolons of the paths are escaped (see `escape_path`).
+/// If the directory is already the last one of the history (of the shell, see `GlobalData::shell`), its line is replaced instead of repeated.
+/// The directories pushed more than `hist_ttl` seconds ago are removed, and only the last `hist_max_lines` directories are kept.
 ///
-pub fn pushd(histpath: &str, path: &str) {
-    let std_path_string = std_path(path);
+pub fn pushd(gdata: &GlobalData, path: &str) {
+    // The path to go to is absolute, so it is empty only if it wasn't found
+    let std_path_string = std_path(path).unwrap_or_default();
     if Path::new(&std_path_string).exists() {
-        fs::write(
-            histpath,
-            format!(
-                "{}\n{};{};{}",
-                fs::read_to_string(histpath)
-                    .read_error(histpath, None)
-                    .trim(),
-                &std_path_string,
-                process::id(),
-                time::SystemTime::now()
-                    .duration_since(time::UNIX_EPOCH)
-                    .internal_error("Time went backwards.", None)
-                    .as_secs(),
-            ),
-        )
-        .write_error(histpath);
+        // The history file also locks the file of the popped directories
+        let Ok(_guard) =
+            lock(&gdata.hist).inspect_err(|er| file_error!("Unable to lock {}: {er}", gdata.hist))
+        else {
+            return;
+        };
+        write_atomic(&gdata.fwd, "").write_error(&gdata.fwd);
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .internal_error("Time went backwards.", None)
+            .as_secs();
+        let content = fs::read_to_string(&gdata.hist).read_error(&gdata.hist, None);
+        let id = gdata
+            .shell_id
+            .clone()
+            .unwrap_or_else(|| process::id().to_string());
+        let escaped = escape_path(&std_path_string);
+        let new_line = format!("{escaped};{id};{now}");
+        let mut lines = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| match split_line(line.trim()).as_slice() {
+                [_, _, secs, ..] => secs
+                    .parse::<u64>()
+                    .map_or(true, |sec| now.saturating_sub(sec) <= gdata.hist_ttl),
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        // Going again to the current directory replaces its line, so that `popd` goes back to another directory
+        let last = lines.iter().rposition(|line| {
+            gdata
+                .shell()
+                .is_none_or(|shell| split_line(line.trim()).get(1) == Some(&shell))
+        });
+        if let Some(idx) = last.filter(|idx| {
+            lines
+                .get(*idx)
+                .and_then(|line| split_line(line.trim()).first().copied())
+                == Some(escaped.as_str())
+        }) {
+            lines.remove(idx);
+        }
+        lines.push(&new_line);
+        let mut data = String::new();
+        for line in lines
+            .iter()
+            .skip(lines.len().saturating_sub(gdata.hist_max_lines))
+        {
+            writeln!(data, "{line}").write_error(&gdata.hist);
+        }
+        write_atomic(&gdata.hist, &data).write_error(&gdata.hist);
     }
 }
 
-/// Pop a directory from the history file.
+/// Pop directories from the history file.
 /// # Arguments
 /// * `histpath` - The path to the history file
+/// * `fwdpath` - The path to the file where the popped directories are saved, for `forward`
+/// * `count` - The number of directories to pop
+/// * `shell` - The id of the shell (see `GlobalData::shell`), to only pop the directories pushed by this shell, or `None` to pop any directory
 /// # Returns
 /// The path of the directory popped.
 /// # Errors
-/// Raises a warning if the history file is empty.
+/// A `PopError` if the history file can't be read, or has no directory to go back to (the directories of the shell are then removed from the history file).
 /// # Example
-/// ```
-/// let path = popd("lib/hist.csv");
+/// ```no_run
+/// use goto::hist::popd;
+/// let path = popd("lib/hist.csv", "lib/fwd.csv", 1, None);
 /// ```
 /// # Note
 /// The history file is a simple text file with the following format:
@@ -64,56 +121,409 @@ pub fn pushd(histpath: &str, path: &str) {
 /// /home/user/folder1;pid1;time1
 /// /home/user/folder2;pid2;time2
 /// ```
-/// Where `pid` is the process id of the process that pushed the directory and `time` is the time in seconds since the Unix Epoch.
+/// Where `pid` is the process id of the process that pushed the directory (or the id of its shell, see `pushd`) and `time` is the time in seconds since the Unix Epoch.
 /// The last line is the most recent directory pushed.
-pub fn popd(histpath: &str) -> String {
+/// If the history has less than `count` older directories, the oldest one is returned, and the number of directories popped is printed.
+/// The malformed lines (without three fields, with an empty path or with a time that isn't an integer) are removed, with an error.
+/// The directories of the other shells are left in the history file.
+pub fn popd(
+    histpath: &str,
+    fwdpath: &str,
+    count: usize,
+    shell: Option<&str>,
+) -> Result<String, PopError> {
+    let _guard = match lock(histpath) {
+        Ok(guard) => guard,
+        Err(er) => return Err(PopError::IoError(er)),
+    };
     let reader = io::BufReader::new(match fs::File::open(histpath) {
         Ok(hist) => hist,
-        Err(er) => {
-            file_error!("Unable to read {histpath}: {er}");
-            return String::new();
-        }
+        Err(er) => return Err(PopError::IoError(er)),
     });
 
-    let mut lines = collections::LinkedList::new();
+    // The lines of the history, with `true` for the ones that can be popped
+    let mut lines = vec![];
+    let mut empty = true;
 
     reader.lines().for_each(|res| match res {
         Ok(line) if !line.trim().is_empty() => {
-            match line.split(';').next() {
-                Some(current) if Path::new(current).exists() => {
-                    lines.push_back(line);
-                }
-                _ => (),
-            };
+            let fields = split_line(&line);
+            // A line is `path;pid;time`: the others can't be popped, and are removed
+            if !matches!(fields.as_slice(), [path, _, secs] if !path.is_empty() && secs.parse::<u64>().is_ok())
+            {
+                data_error!("Invalid line in {histpath}, removed: {line}");
+                return;
+            }
+            let mine = shell.is_none_or(|id| fields.get(1) == Some(&id));
+            let exists = fields
+                .first()
+                .is_some_and(|current| Path::new(&unescape_path(current)).exists());
+            empty &= !mine;
+            if exists || !mine {
+                lines.push((line, mine));
+            }
         }
         Err(er) => data_error!("Unable to read a line in {histpath}: {er}"),
         _ => (),
     });
 
-    let mut writer = BufWriter::new(match fs::File::create(histpath) {
-        Ok(hist) => hist,
-        Err(er) => {
-            file_error!("Unable to write to {histpath}: {er}");
-            return String::new();
+    let mut mine = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (_, is_mine))| is_mine.then_some(idx))
+        .collect::<Vec<_>>();
+    // The last line is the current directory
+    let available = mine.len().saturating_sub(1);
+    if available == 0 {
+        let mut data = String::new();
+        for (line, _) in lines.iter().filter(|(_, is_mine)| !is_mine) {
+            writeln!(data, "{line}").write_error(histpath);
         }
-    });
+        write_atomic(histpath, &data).write_error(histpath);
+        return Err(if empty {
+            PopError::EmptyHistory
+        } else {
+            PopError::NoValidEntry
+        });
+    }
+    let popped = count.min(available);
+    if popped < count {
+        user_error!("Only {popped} directories popped: there is no older directory in the history");
+    }
+    let mut fwd = fs::read_to_string(fwdpath).read_error(fwdpath, None);
+    for idx in (0..popped).filter_map(|_| mine.pop()) {
+        let (line, _) = lines.remove(idx);
+        writeln!(fwd, "{line}").write_error(fwdpath);
+    }
+    write_atomic(fwdpath, &fwd).write_error(fwdpath);
+
+    let mut data = String::new();
+    for (line, _) in &lines {
+        writeln!(data, "{line}").write_error(histpath);
+    }
+    write_atomic(histpath, &data).write_error(histpath);
+    Ok(unescape_path(
+        mine.last()
+            .and_then(|idx| lines.get(*idx))
+            .and_then(|(last_line, _)| split_line(last_line).first().copied())
+            .internal_error("Checked if path was correct, but isn't found", None),
+    ))
+}
+
+/// Go back to the last directory popped by `popd`.
+/// # Arguments
+/// * `histpath` - The path to the history file
+/// * `fwdpath` - The path to the file of the popped directories
+/// # Returns
+/// The path of the directory, or `None` if no directory was popped (or if it doesn't exist anymore).
+/// # Example
+/// ```no_run
+/// use goto::hist::forward;
+/// let path = forward("lib/hist.csv", "lib/fwd.csv");
+/// ```
+/// # Note
+/// The directory is removed from the file of the popped directories, and pushed back to the history file.
+pub fn forward(histpath: &str, fwdpath: &str) -> Option<String> {
+    let Ok(_guard) =
+        lock(histpath).inspect_err(|er| file_error!("Unable to lock {histpath}: {er}"))
+    else {
+        return None;
+    };
+    let content = fs::read_to_string(fwdpath).read_error(fwdpath, None);
+    let mut lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let Some(last_line) = lines.pop() else {
+        return None;
+    };
+    let mut data = String::new();
+    for line in lines {
+        writeln!(data, "{line}").write_error(fwdpath);
+    }
+    write_atomic(fwdpath, &data).write_error(fwdpath);
+
+    let path = unescape_path(split_line(last_line).first().copied().unwrap_or_default());
+    if Path::new(&path).exists() {
+        write_atomic(
+            histpath,
+            &format!(
+                "{}\n{last_line}",
+                fs::read_to_string(histpath)
+                    .read_error(histpath, None)
+                    .trim()
+            ),
+        )
+        .write_error(histpath);
+        Some(path)
+    } else {
+        data_error!("The directory to go forward to, {path}, doesn't exist anymore");
+        None
+    }
+}
+
+/// Print the history of directories, from the most recent one.
+/// # Arguments
+/// * `histpath` - The path to the history file
+/// # Note
+/// The directories that don't exist anymore are not printed. The lines are printed in the following format:
+/// ```text
+///   1. /home/user/folder2 (5 min ago)
+///   2. /home/user/folder1 (2 h ago)
+/// ```
+pub fn history(histpath: &str) -> ! {
+    let now = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .internal_error("Time went backwards.", None)
+        .as_secs();
+    let content = fs::read_to_string(histpath).read_error(histpath, None);
+    let mut data = String::new();
+    for (idx, (path, secs)) in content
+        .lines()
+        .rev()
+        .filter_map(|line| match split_line(line.trim()).as_slice() {
+            [path, _, secs, ..] => Some((unescape_path(path), secs.parse::<u64>().ok())),
+            _ => None,
+        })
+        .filter(|(path, _)| Path::new(path).exists())
+        .enumerate()
+    {
+        let ago = secs.map_or_else(
+            || String::from("unknown time"),
+            |sec| age(now.saturating_sub(sec)),
+        );
+        writeln!(data, "{:>3}. {path} ({ago})", idx.saturating_add(1)).write_error("lines");
+    }
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{data}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-    lines.pop_back();
-    lines.pop_back().map_or_else(
-        || {
-            file_error!("{histpath} is empty");
-            String::new()
-        },
-        |last_line| {
-            for line in lines {
-                writeln!(writer, "{line}").write_error(histpath);
+/// Rewrite the history file without the directories that don't exist anymore, and with each directory only once.
+/// # Arguments
+/// * `histpath` - The path to the history file
+/// # Note
+/// `popd` skips the directories that don't exist, but only removes them when it finds nothing to pop: they are removed here.
+/// Of the lines of a directory, only the most recent one is kept (whatever the shell that pushed it), at its place in the history.
+/// The lines that can't be read are kept as is. The number of lines removed is printed.
+pub fn clean_history(histpath: &str) -> ! {
+    // The lock is released before exiting
+    let (missing, duplicates) = {
+        let Ok(_guard) =
+            lock(histpath).inspect_err(|er| file_error!("Unable to lock {histpath}: {er}"))
+        else {
+            #[expect(
+                clippy::exit,
+                reason = "the process ends with the status of the command"
+            )]
+            process::exit(1);
+        };
+        let mut missing: usize = 0;
+        let mut duplicates: usize = 0;
+        let content = fs::read_to_string(histpath).read_error(histpath, None);
+        // The lines kept, with their path and their time if they can be read
+        let mut lines: Vec<(&str, Option<(String, u64)>)> = vec![];
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let Some((path, secs)) = (match split_line(line.trim()).as_slice() {
+                [path, _, secs, ..] => secs.parse::<u64>().ok().map(|sec| {
+                    let full_path = unescape_path(path);
+                    (std_path(&full_path).unwrap_or(full_path), sec)
+                }),
+                _ => None,
+            }) else {
+                lines.push((line, None));
+                continue;
+            };
+            if !Path::new(&path).exists() {
+                missing = missing.saturating_add(1);
+                continue;
+            }
+            let previous = lines
+                .iter()
+                .position(|(_, entry)| entry.as_ref().is_some_and(|(pth, _)| *pth == path));
+            if let Some(idx) = previous {
+                duplicates = duplicates.saturating_add(1);
+                let older = lines
+                    .get(idx)
+                    .and_then(|(_, entry)| entry.as_ref())
+                    .is_some_and(|(_, old)| *old <= secs);
+                if !older {
+                    continue;
+                }
+                lines.remove(idx);
             }
-            writeln!(writer, "{last_line}").write_error(histpath);
-            last_line
-                .split(';')
-                .next()
-                .internal_error("Checked if path was correct, but isn't found", None)
-                .to_owned()
-        },
-    )
+            lines.push((line, Some((path, secs))));
+        }
+        let mut data = String::new();
+        for (line, _) in &lines {
+            writeln!(data, "{line}").write_error(histpath);
+        }
+        write_atomic(histpath, &data).write_error(histpath);
+        (missing, duplicates)
+    };
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("Removed missing directories: {missing}");
+        println!("Removed duplicate directories: {duplicates}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Format a duration in a human-readable way.
+/// # Arguments
+/// * `secs` - The duration in seconds
+/// # Returns
+/// The duration in the largest unit it contains, e.g. `3 days ago`.
+pub fn age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs} s ago"),
+        60..3600 => format!("{} min ago", secs.checked_div(60).unwrap_or_default()),
+        3600..86400 => format!("{} h ago", secs.checked_div(3600).unwrap_or_default()),
+        _ => format!("{} days ago", secs.checked_div(86400).unwrap_or_default()),
+    }
+}
+
+/// Save a directory in the return file, so that the next `-pop` or `-return` goes back to it.
+/// # Arguments
+/// * `retpath` - The path to the return file
+/// * `path` - The path to the directory to return to
+/// # Example
+/// ```no_run
+/// use goto::hist::set_return;
+/// set_return("lib/ret.csv", "/home/user/folder");
+/// ```
+/// # Note
+/// The return file only contains one directory: the previous one is overwritten.
+pub fn set_return(retpath: &str, path: &str) {
+    write_atomic(retpath, &std_path(path).unwrap_or_else(|_| path.to_owned())).write_error(retpath);
+}
+
+/// Take the directory saved in the return file, and empty the file.
+/// # Arguments
+/// * `retpath` - The path to the return file
+/// # Returns
+/// The path of the directory to return to, or `None` if there is none or if it doesn't exist anymore.
+/// # Example
+/// ```no_run
+/// use goto::hist::take_return;
+/// let path = take_return("lib/ret.csv");
+/// ```
+pub fn take_return(retpath: &str) -> Option<String> {
+    let content = fs::read_to_string(retpath).read_error(retpath, None);
+    let path = content.trim();
+    if path.is_empty() {
+        return None;
+    }
+    write_atomic(retpath, "").write_error(retpath);
+    if Path::new(path).exists() {
+        Some(path.to_owned())
+    } else {
+        data_error!("The directory to return to, {path}, doesn't exist anymore");
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Function to get the data of the program, with history files unique to the test.
+    fn temp_gdata(name: &str) -> GlobalData<'static> {
+        let file = |kind: &str| {
+            env::temp_dir()
+                .join(format!("goto-{}-{name}-{kind}.csv", process::id()))
+                .to_string_lossy()
+                .into_owned()
+        };
+        GlobalData {
+            hist: file("hist"),
+            fwd: file("fwd"),
+            shell_id: Some(String::from("s")),
+            ..GlobalData::default()
+        }
+    }
+
+    /// Function to read the paths of a history file, and remove the history files.
+    fn take_paths(gdata: &GlobalData) -> Vec<String> {
+        let content = fs::read_to_string(&gdata.hist).unwrap_or_default();
+        for file in [&gdata.hist, &gdata.fwd] {
+            drop(fs::remove_file(file));
+        }
+        content
+            .lines()
+            .filter_map(|line| split_line(line).first().map(|path| unescape_path(path)))
+            .collect()
+    }
+
+    #[test]
+    fn pushd_keeps_the_newest_lines() {
+        let gdata = GlobalData {
+            hist_ttl: 100,
+            hist_max_lines: 3,
+            ..temp_gdata("pushd")
+        };
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |dur| dur.as_secs());
+        let recent = now.saturating_sub(10);
+        let content = format!("/expired;s;1\n/old;s;{now}\n/a;s;{recent}\n/b;s;{now}\n");
+        fs::write(&gdata.hist, content).write_error(&gdata.hist);
+        let tmp = std_path(&env::temp_dir().to_string_lossy()).unwrap_or_default();
+        pushd(&gdata, &tmp);
+        assert_eq!(take_paths(&gdata), ["/a", "/b", tmp.as_str()]);
+    }
+
+    #[test]
+    fn pushd_keeps_a_path_once() {
+        let gdata = temp_gdata("twice");
+        let tmp = std_path(&env::temp_dir().to_string_lossy()).unwrap_or_default();
+        pushd(&gdata, &tmp);
+        pushd(&gdata, &tmp);
+        assert_eq!(take_paths(&gdata), [tmp.as_str()]);
+    }
+
+    #[test]
+    fn pushd_drops_the_expired_lines() {
+        let gdata = GlobalData {
+            hist_ttl: 100,
+            ..temp_gdata("expired")
+        };
+        let content = "/expired;s;1\n/broken;s\n/unknown;s;soon\n";
+        fs::write(&gdata.hist, content).write_error(&gdata.hist);
+        let tmp = std_path(&env::temp_dir().to_string_lossy()).unwrap_or_default();
+        pushd(&gdata, &tmp);
+        // The lines that can't be read are kept, and removed by `clean_history`
+        assert_eq!(take_paths(&gdata), ["/broken", "/unknown", tmp.as_str()]);
+    }
+
+    #[test]
+    fn popd_removes_a_truncated_line() {
+        let gdata = temp_gdata("truncated");
+        let tmp = std_path(&env::temp_dir().to_string_lossy()).unwrap_or_default();
+        let content = format!("/;s;1\n{tmp};s\n{tmp};s;2\n");
+        fs::write(&gdata.hist, content).write_error(&gdata.hist);
+        fs::write(&gdata.fwd, "").write_error(&gdata.fwd);
+        let popped = popd(&gdata.hist, &gdata.fwd, 1, gdata.shell_id.as_deref());
+        let fwd = fs::read_to_string(&gdata.fwd).unwrap_or_default();
+        assert_eq!(popped.ok().as_deref(), Some("/"));
+        assert_eq!(fwd, format!("{tmp};s;2\n"));
+        assert_eq!(take_paths(&gdata), ["/"]);
+    }
 }
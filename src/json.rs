@@ -0,0 +1,261 @@
+use crate::errors::WriteError as _;
+use core::fmt::Write as _;
+use core::{iter, str};
+
+/// Characters of a JSON text, with the possibility to look at the next one without consuming it.
+type Chars<'text> = iter::Peekable<str::Chars<'text>>;
+
+/// Value of a JSON text
+/// Is used by `-import-json` to read the JSON written by `-export-json`.
+#[derive(Debug)]
+pub enum Json {
+    /// An array of values.
+    Array(Vec<Self>),
+    /// `true` or `false`.
+    Bool(bool),
+    /// `null`.
+    Null,
+    /// A number, as written in the JSON (e.g. `14` or `-1.5e3`).
+    Number(String),
+    /// An object, with its fields in the order of the JSON.
+    Object(Vec<(String, Self)>),
+    /// A string, unescaped.
+    String(String),
+}
+
+impl Json {
+    /// Get the values of an array, or `None` if the value isn't an array.
+    pub fn as_array(&self) -> Option<&[Self]> {
+        let Self::Array(values) = self else {
+            return None;
+        };
+        Some(values)
+    }
+
+    /// Get the value of a boolean, or `None` if the value isn't a boolean.
+    pub const fn as_bool(&self) -> Option<bool> {
+        let Self::Bool(value) = self else {
+            return None;
+        };
+        Some(*value)
+    }
+
+    /// Get the text of a string, or `None` if the value isn't a string.
+    pub fn as_str(&self) -> Option<&str> {
+        let Self::String(text) = self else {
+            return None;
+        };
+        Some(text)
+    }
+
+    /// Get the value of a number, or `None` if the value isn't a positive integer that fits in a `u32`.
+    pub fn as_u32(&self) -> Option<u32> {
+        let Self::Number(number) = self else {
+            return None;
+        };
+        number.parse::<u32>().ok()
+    }
+
+    /// Get the value of a field of an object.
+    /// # Returns
+    /// The value of the field, or `None` if the value isn't an object or has no such field.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        let Self::Object(fields) = self else {
+            return None;
+        };
+        fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Function to format a text as a JSON string.
+/// # Arguments
+/// * `text` - The text to format
+/// # Returns
+/// The text between quotes, with the quotes, the backslashes and the control characters escaped.
+pub fn escape(text: &str) -> String {
+    let mut json = String::from('"');
+    for chr in text.chars() {
+        match chr {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            _ if chr.is_control() => {
+                write!(json, "\\u{:04x}", u32::from(chr)).write_error("json");
+            }
+            _ => json.push(chr),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Function to parse a JSON text.
+/// # Arguments
+/// * `text` - The JSON text
+/// # Returns
+/// The value of the JSON.
+/// # Errors
+/// The reason why the text isn't valid JSON.
+/// # Example
+/// ```
+/// use goto::json::{parse, Json};
+/// let json = parse(r#"{"version": 1, "dirs": []}"#).unwrap();
+/// assert!(json.get("version").and_then(Json::as_u32) == Some(1));
+/// ```
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut chars = text.chars().peekable();
+    match value(&mut chars) {
+        Ok(json) => {
+            skip_spaces(&mut chars);
+            chars.peek().map_or(Ok(json), |chr| {
+                Err(format!(
+                    "Unexpected character <{chr}> after the end of the JSON"
+                ))
+            })
+        }
+        Err(er) => Err(er),
+    }
+}
+
+/// Function to skip the whitespaces between the tokens.
+fn skip_spaces(chars: &mut Chars) {
+    while chars.next_if(|chr| chr.is_whitespace()).is_some() {}
+}
+
+/// Function to parse any JSON value, starting at the next token.
+fn value(chars: &mut Chars) -> Result<Json, String> {
+    skip_spaces(chars);
+    match chars.peek() {
+        Some('{') => object(chars),
+        Some('[') => array(chars),
+        Some('"') => string(chars).map(Json::String),
+        Some('t') => literal(chars, "true", Json::Bool(true)),
+        Some('f') => literal(chars, "false", Json::Bool(false)),
+        Some('n') => literal(chars, "null", Json::Null),
+        Some(chr) if *chr == '-' || chr.is_ascii_digit() => number(chars),
+        Some(chr) => Err(format!("Unexpected character <{chr}>")),
+        None => Err(String::from("Unexpected end of the JSON")),
+    }
+}
+
+/// Function to parse `true`, `false` or `null`.
+fn literal(chars: &mut Chars, word: &str, json: Json) -> Result<Json, String> {
+    if word.chars().all(|chr| chars.next_if_eq(&chr).is_some()) {
+        Ok(json)
+    } else {
+        Err(format!("Invalid literal: expected <{word}>"))
+    }
+}
+
+/// Function to parse a number, kept as written (see `Json::Number`).
+fn number(chars: &mut Chars) -> Result<Json, String> {
+    let mut number = String::new();
+    while let Some(chr) =
+        chars.next_if(|chr| chr.is_ascii_digit() || matches!(chr, '-' | '+' | '.' | 'e' | 'E'))
+    {
+        number.push(chr);
+    }
+    match number.parse::<f64>() {
+        Ok(_) => Ok(Json::Number(number)),
+        Err(er) => Err(format!("Invalid number <{number}>: {er}")),
+    }
+}
+
+/// Function to parse a string, starting at its opening quote.
+fn string(chars: &mut Chars) -> Result<String, String> {
+    chars.next();
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                Some('b') => text.push('\u{8}'),
+                Some('f') => text.push('\u{c}'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(chr) => text.push(chr),
+                        None => return Err(format!("Invalid escape <\\u{hex}> in a string")),
+                    }
+                }
+                Some(chr @ ('"' | '\\' | '/')) => text.push(chr),
+                Some(chr) => return Err(format!("Invalid escape <\\{chr}> in a string")),
+                None => return Err(String::from("Unterminated string")),
+            },
+            Some(chr) => text.push(chr),
+            None => return Err(String::from("Unterminated string")),
+        }
+    }
+}
+
+/// Function to parse an array, starting at its opening bracket.
+fn array(chars: &mut Chars) -> Result<Json, String> {
+    chars.next();
+    let mut values = vec![];
+    skip_spaces(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(Json::Array(values));
+    }
+    loop {
+        match value(chars) {
+            Ok(json) => values.push(json),
+            Err(er) => return Err(er),
+        }
+        skip_spaces(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some(']') => return Ok(Json::Array(values)),
+            _ => {
+                return Err(String::from(
+                    "Expected <,> or <]> after a value of an array",
+                ))
+            }
+        }
+    }
+}
+
+/// Function to parse an object, starting at its opening brace.
+fn object(chars: &mut Chars) -> Result<Json, String> {
+    chars.next();
+    let mut fields = vec![];
+    skip_spaces(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(Json::Object(fields));
+    }
+    loop {
+        skip_spaces(chars);
+        if chars.peek() != Some(&'"') {
+            return Err(String::from("Expected the name of a field of an object"));
+        }
+        let name = match string(chars) {
+            Ok(name) => name,
+            Err(er) => return Err(er),
+        };
+        skip_spaces(chars);
+        if chars.next_if_eq(&':').is_none() {
+            return Err(format!("Expected <:> after the field <{name}>"));
+        }
+        match value(chars) {
+            Ok(json) => fields.push((name, json)),
+            Err(er) => return Err(er),
+        }
+        skip_spaces(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => return Ok(Json::Object(fields)),
+            _ => {
+                return Err(String::from(
+                    "Expected <,> or <}> after a field of an object",
+                ))
+            }
+        }
+    }
+}
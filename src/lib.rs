@@ -0,0 +1,1036 @@
+#![warn(
+    clippy::all,
+    clippy::pedantic,
+    clippy::restriction,
+    clippy::nursery,
+    clippy::cargo
+)]
+#![feature(stmt_expr_attributes)]
+#![allow(
+    clippy::blanket_clippy_restriction_lints,
+    reason = "the restriction lints are enabled together, and the ones that don't fit are allowed below"
+)]
+#![allow(
+    clippy::inline_modules,
+    reason = "the unit tests are next to the code they test"
+)]
+#![allow(
+    clippy::implicit_return,
+    clippy::single_call_fn,
+    reason = "functions end with their value, and are split for readability"
+)]
+#![allow(clippy::string_add, reason = "strings are built with `+`")]
+#![allow(
+    clippy::pattern_type_mismatch,
+    reason = "references are matched with default binding modes"
+)]
+#![allow(
+    clippy::missing_inline_in_public_items,
+    clippy::must_use_candidate,
+    clippy::exhaustive_structs,
+    clippy::exhaustive_enums,
+    reason = "the library is the internals of the binary, not a stable interface"
+)]
+///////////////////////////////: Documentation  :///////////////////////////////
+
+//! Library of `goto`.
+//!
+//! Contains the parsing of the command line, and the functions to read and write the supported directories and the history of directories.
+//! The binary (`main.rs`) is a thin layer over it, that reads `env::args`, and prints the directory to go to for the shell wrappers.
+//! # Example
+//! ```no_run
+//! let gdata = goto::GlobalData::default();
+//! let (args1, _, _) = goto::parse_args(&gdata, &[String::from("-get"), String::from("foo")]).unwrap_or_default();
+//! let path = goto::dirs::read(&gdata.dirs, &args1, &gdata, false, false);
+//! ```
+
+///////////////////////////////: Imports  :///////////////////////////////
+
+/// This module contains the structure of the options of the goto command that access the file containing the shorcuts. (-get, -add, -edit, ...)
+pub mod commands;
+/// This module contains the functions to push and pop directories from the history file.
+pub mod dirs;
+/// This module contains all the error functions avalaible in all the program.
+pub mod errors;
+/// This module contains the functions to read and write the supported directories, their shortcuts and their usage.
+pub mod hist;
+/// This module contains a minimal JSON parser and formatter, for `-export-json` and `-import-json`.
+pub mod json;
+
+use crate::commands::{AppendDefault as _, Cmd, NoCurrentDir, Opt, ShortPath, ToCmd as _};
+use crate::errors::{InteractionError as _, WriteError as _};
+
+use core::{fmt, iter, str};
+use std::{collections, env, fs, path};
+
+///////////////////////////////: Global static data  :///////////////////////////////
+
+/// Commands that modify the file of supported shortcuts, with their number of arguments (see `GlobalData::argcs`).
+const ARGCS: [(&str, usize); 15] = [
+    ("-add", 2),
+    ("-remove", 1),
+    ("-delete", 1),
+    ("-edit", 2),
+    ("-reset", 0),
+    ("-del", 1),
+    ("-decrement", 1),
+    ("-age", 1),
+    ("-clone", 2),
+    ("-replace-shortcut", 2),
+    ("-prune", 0),
+    ("-set", 2),
+    ("-freeze-priority", 1),
+    ("-unfreeze-priority", 1),
+    ("-move", 2),
+];
+
+/// Commands that don't need the file of supported shortcuts and take arguments, with their maximum number of arguments (see `GlobalData::nargcs`).
+const NARGCS: [(&str, usize); 17] = [
+    ("-rank", 1),
+    ("-top", 1),
+    ("-recent", 1),
+    ("-search", 1),
+    ("-pop", 1),
+    ("-state", 1),
+    ("-stats", 1),
+    ("-sweep", 1),
+    ("-doctor", 1),
+    ("-import-zoxide", 1),
+    ("-export-json", 1),
+    ("-import-json", 1),
+    ("-completions", 1),
+    ("-init", 1),
+    ("--weight", 1),
+    ("-version", 1),
+    ("-help", 1),
+];
+
+/// Aliases of the commands, with the command they stand for (see `GlobalData::aliass`).
+const ALIASES: [(&str, &str); 25] = [
+    ("-a", "-add"),
+    ("-e", "-edit"),
+    ("-rm", "-remove"),
+    ("-interactive-remove", "-remove"),
+    ("-del", "-delete"),
+    ("-rename", "-replace-shortcut"),
+    ("-mv", "-replace-shortcut"),
+    ("-mvpath", "-move"),
+    ("-gc", "-prune"),
+    ("-decr", "-decrement"),
+    ("-p", "-pop"),
+    ("<", "-pop"),
+    ("-hist", "-history"),
+    ("-fwd", "-forward"),
+    ("?", "-state"),
+    ("-nc", "-noclear"),
+    ("!", "-noclear"),
+    ("-c", "-code"),
+    ("%", "-still"),
+    ("-g", "-get"),
+    ("-cls", "-clear"),
+    ("-y", "-force"),
+    ("-why", "-explain"),
+    ("-V", "-version"),
+    ("-h", "-help"),
+];
+
+/// Commands that don't require reading the file of supported shortcuts (see `GlobalData::no_dirs`).
+const NO_DIRS: &[&str] = &[
+    "-noclear",
+    "-code",
+    "-still",
+    "-pop",
+    "-temp",
+    "-return",
+    "-forward",
+    "-state",
+    "-clear",
+    "-force",
+    "-explain",
+    "-raw",
+    "-rank",
+    "-top",
+    "-recent",
+    "-search",
+    "-shortcuts",
+    "-verify-shell",
+    "-export-env",
+    "-config",
+    "-bulk-edit",
+    "-stats",
+    "-sweep",
+    "-doctor",
+    "-history",
+    "-clean-history",
+    "-import-zoxide",
+    "-export-json",
+    "-import-json",
+    "-completions",
+    "-init",
+    "-undo",
+    "--weight",
+    "-version",
+    "-help",
+];
+
+/// Commands that print data to stdout, and thus must not clear the terminal (see `GlobalData::outputs`).
+const OUTPUTS: &[&str] = &[
+    "-explain",
+    "-raw",
+    "-state",
+    "-rank",
+    "-top",
+    "-recent",
+    "-search",
+    "-shortcuts",
+    "-verify-shell",
+    "-export-env",
+    "-config",
+    "-stats",
+    "-sweep",
+    "-doctor",
+    "-history",
+    "-clean-history",
+    "-import-zoxide",
+    "-export-json",
+    "-import-json",
+    "-completions",
+    "-init",
+    "-version",
+    "-help",
+];
+
+/// Flags of the commands, that are read with the command before them (see `GlobalData::flags`).
+const FLAGS: &[&str] = &[
+    "--after",
+    "--allow-missing",
+    "--to",
+    "--global",
+    "--local",
+    "--broken",
+    "--dry-run",
+    "--fix",
+    "-i",
+];
+
+#[derive(Debug)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "the settings are independent switches"
+)]
+/// Structure to contain all the static data of the program.
+pub struct GlobalData<'global> {
+    /// Gives the alias of every supported command.
+    pub aliass: collections::HashMap<&'global str, &'global str>,
+    /// Gives the number of arguments for each supported command (except the basic `goto` command that can take 0, 1 or 2 arguments).
+    pub argcs: collections::HashMap<&'global str, usize>,
+    /// `true` to resolve the symbolic links of the paths given to `-add` and `-edit` (read from `GOTO_CANONICALIZE`).
+    pub canonicalize: bool,
+    /// `false` to find a shortcut in another case if it doesn't exist with the given case (read from `GOTO_CASE_SENSITIVE`).
+    pub case_sensitive: bool,
+    /// Path to the file containing the list of shortcuts defined by the user with the `-add` and `-edit` commands.
+    pub dirs: String,
+    /// Editor opened by the `-code` command (read from `GOTO_EDITOR`).
+    pub editor: String,
+    /// Gives the values starting with `-` that the commands of `argcs` accept (e.g. `--after`), and that are thus not invalid options (see `parse_args`).
+    pub flags: &'global [&'global str],
+    /// Folder of the data files, ending with a `/` (see `data_folder`): `ret` and `fwd` are always in it, and `dirs` and `hist` unless they are set.
+    pub folder: String,
+    /// Maximum number of typos (Levenshtein distance) between a shortcut and the given one in the fuzzy search (read from `GOTO_FUZZY_THRESHOLD`).
+    pub fuzzy_threshold: usize,
+    /// Weight of the usage of a folder in the fuzzy search, compared to the distance between the shortcuts (0 to only use the distance, read from `GOTO_FUZZY_WEIGHT`).
+    pub fuzzy_weight: f64,
+    /// Path to the file containing the directories popped by the `-pop` command, for the `-forward` command.
+    pub fwd: String,
+    /// Path to the file containing the history for the `-pop` command.
+    pub hist: String,
+    /// Maximum number of directories in the history: the oldest ones are removed first (read from `GOTO_HIST_MAX_LINES`).
+    pub hist_max_lines: usize,
+    /// Number of seconds after which a directory of the history is removed from it (read from `GOTO_HIST_TTL`).
+    pub hist_ttl: u64,
+    /// When a folder is used, `incr` is used to increment the usage of the folder (read from `GOTO_INCR`, or from `--weight` for one call, see `parse_incr`).
+    pub incr: u32,
+    /// `true` to choose the directory in a menu when several shortcuts start with the given one (read from `GOTO_INTERACTIVE`).
+    pub interactive: bool,
+    /// Maximum priority of a folder: once it is reached, all the priorities are halved, so that their order is kept (read from `GOTO_MAX_PRIORITY`).
+    pub max_priority: u32,
+    /// Gives the maximum number of arguments of the `no_dirs` commands that take some.
+    pub nargcs: collections::HashMap<&'global str, usize>,
+    /// `true` to never clear the terminal, like `-noclear` (read from `GOTO_NO_CLEAR`).
+    pub no_clear: bool,
+    /// Gives the arguments that don't require reading `lib/dirs.csv`.
+    pub no_dirs: &'global [&'global str],
+    /// Separator of the fields of the output read by the shell wrappers (read from `GOTO_OUTPUT_SEP`, the unit separator `\x1f` by default, as it can't be in a path).
+    pub output_sep: String,
+    /// Gives the `no_dirs` arguments that print data to stdout, and thus must not clear the terminal.
+    pub outputs: &'global [&'global str],
+    /// `true` to only pop the directories pushed by the current shell (read from `GOTO_PER_SHELL_HISTORY`, see `shell`).
+    pub per_shell_history: bool,
+    /// Path to the file containing the directory saved by the `-temp` command, for the `-return` and `-pop` commands.
+    pub ret: String,
+    /// Paths to the shared files of shortcuts, only searched to navigate when `dirs` has no exact match, and never written (read from `GOTO_DIRS_PATH`, whose first file is `dirs`).
+    pub shared: Vec<String>,
+    /// Id of the current shell, set by the wrapper of the shell (read from `GOTO_SHELL_ID`).
+    pub shell_id: Option<String>,
+    /// Strategies used to find the directory of a shortcut, in order (read from `GOTO_MATCH`).
+    pub tiers: Vec<dirs::Tier>,
+    /// `true` if the OS is unix, `false` if the OS is windows.
+    pub unix: bool,
+}
+
+/// Defining the data for `GlobalData`.
+impl Default for GlobalData<'_> {
+    fn default() -> Self {
+        let unix = cfg!(target_os = "linux");
+        assert!(unix || cfg!(target_os = "windows"), "Unsupported OS");
+
+        let libfolder = data_folder(unix);
+
+        let mut tiers = env::var("GOTO_MATCH")
+            .map(|spec| dirs::Tier::parse_list(&spec))
+            .unwrap_or_default();
+        if tiers.is_empty() {
+            tiers = vec![dirs::Tier::Exact, dirs::Tier::Fuzzy];
+        }
+
+        // The first file of `GOTO_DIRS_PATH` is the personal one, and the others are shared
+        let mut dirs_path = env::var_os("GOTO_DIRS_PATH")
+            .map(|value| {
+                env::split_paths(&value)
+                    .filter_map(|file| file.to_str().map(str::to_owned))
+                    .filter(|file| !file.trim().is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+            .into_iter();
+        // `GOTO_DIRS` and `GOTO_HIST` take precedence, e.g. to keep separate shortcuts per project
+        let dirs = dirs_path
+            .next()
+            .or_else(|| env_value("GOTO_DIRS"))
+            .unwrap_or_else(|| libfolder.clone() + "dirs.csv");
+        let shared = dirs_path.collect();
+        let hist = env_value("GOTO_HIST").unwrap_or_else(|| libfolder.clone() + "hist.csv");
+        let ret = libfolder.clone() + "ret.csv";
+        let fwd = libfolder.clone() + "fwd.csv";
+
+        Self {
+            dirs,
+            shared,
+            hist,
+            ret,
+            fwd,
+            folder: libfolder,
+            incr: env::var("GOTO_INCR").map_or(10, |value| parse_incr(&value, "GOTO_INCR")),
+            max_priority: parse_number(
+                env::var("GOTO_MAX_PRIORITY").ok(),
+                "GOTO_MAX_PRIORITY",
+                1_000_000,
+                |max: u32| max > 0,
+                "an integer between 1 and 4294967295",
+            ),
+            hist_ttl: parse_number(
+                env::var("GOTO_HIST_TTL").ok(),
+                "GOTO_HIST_TTL",
+                30 * 24 * 3600,
+                |ttl: u64| ttl > 0,
+                "a positive number of seconds",
+            ),
+            hist_max_lines: parse_number(
+                env::var("GOTO_HIST_MAX_LINES").ok(),
+                "GOTO_HIST_MAX_LINES",
+                1000,
+                |max: usize| max > 0,
+                "a positive number of lines",
+            ),
+            per_shell_history: env_flag("GOTO_PER_SHELL_HISTORY"),
+            shell_id: env::var("GOTO_SHELL_ID")
+                .ok()
+                .filter(|id| !id.trim().is_empty() && !id.contains([';', '\n', '\r'])),
+            fuzzy_weight: parse_number(
+                env::var("GOTO_FUZZY_WEIGHT").ok(),
+                "GOTO_FUZZY_WEIGHT",
+                0.5,
+                |weight: f64| weight.is_finite() && weight.is_sign_positive(),
+                "a positive number",
+            ),
+            fuzzy_threshold: parse_number(
+                env::var("GOTO_FUZZY_THRESHOLD").ok(),
+                "GOTO_FUZZY_THRESHOLD",
+                2,
+                |_: usize| true,
+                "a number of typos",
+            ),
+            case_sensitive: env_flag("GOTO_CASE_SENSITIVE"),
+            editor: env_value("GOTO_EDITOR").unwrap_or_else(|| String::from("code")),
+            canonicalize: !env::var("GOTO_CANONICALIZE")
+                .is_ok_and(|value| value == "0" || value.eq_ignore_ascii_case("false")),
+            interactive: env_flag("GOTO_INTERACTIVE"),
+            no_clear: env_flag("GOTO_NO_CLEAR"),
+            output_sep: env::var("GOTO_OUTPUT_SEP")
+                .ok()
+                .filter(|sep| !sep.is_empty() && !sep.contains(['\n', '\r']))
+                .unwrap_or_else(|| String::from("\x1f")),
+            tiers,
+            unix,
+            argcs: collections::HashMap::from(ARGCS),
+            aliass: collections::HashMap::from(ALIASES),
+            no_dirs: NO_DIRS,
+            nargcs: collections::HashMap::from(NARGCS),
+            outputs: OUTPUTS,
+            flags: FLAGS,
+        }
+    }
+}
+
+impl GlobalData<'_> {
+    /// Create the files of the program that don't exist yet.
+    /// # Note
+    /// On a fresh install, the files (and their folders, e.g. for `GOTO_DIRS`) are created empty.
+    /// This isn't done by `GlobalData::default`, so that the commands that only print (e.g. `-version`) don't touch the disk.
+    pub fn create_files(&self) {
+        for file in [&self.dirs, &self.hist, &self.ret, &self.fwd] {
+            let file_path = path::Path::new(file);
+            if !file_path.exists() {
+                if let Some(parent) = file_path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty() && !parent.exists())
+                {
+                    fs::create_dir_all(parent).write_error(file);
+                }
+                fs::write(file, "").write_error(file);
+            }
+        }
+    }
+
+    /// Check if a shortcut can't be used, because it would be read as a command.
+    /// # Arguments
+    /// * `short` - The shortcut to check
+    /// # Returns
+    /// `true` if the shortcut starts with `-`, or is a command or an alias (e.g. `?` or `!`).
+    pub fn reserved(&self, short: &str) -> bool {
+        short.starts_with('-')
+            || short == "features"
+            || self.argcs.contains_key(short)
+            || self.aliass.contains_key(short)
+            || self.no_dirs.contains(&short)
+    }
+
+    /// Get the settings of the program, as the environment variables that set them (see `-export-env`).
+    /// # Returns
+    /// The names of the variables without the `GOTO_` prefix, with their resolved values.
+    /// # Note
+    /// `self` is destructured, so that a new field can't be forgotten here.
+    #[expect(
+        clippy::unneeded_field_pattern,
+        reason = "every field is listed so that a new setting is not forgotten"
+    )]
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let Self {
+            dirs,
+            shared,
+            hist,
+            ret: _,
+            fwd: _,
+            folder,
+            incr,
+            max_priority,
+            hist_ttl,
+            hist_max_lines,
+            per_shell_history,
+            // The id of the shell is set by each wrapper, and isn't part of the configuration
+            shell_id: _,
+            fuzzy_weight,
+            fuzzy_threshold,
+            case_sensitive,
+            editor,
+            canonicalize,
+            interactive,
+            no_clear,
+            output_sep,
+            tiers,
+            // The tables of the commands aren't settings
+            argcs: _,
+            unix: _,
+            aliass: _,
+            no_dirs: _,
+            nargcs: _,
+            outputs: _,
+            flags: _,
+        } = self;
+        vec![
+            ("DIR", folder.clone()),
+            ("DIRS", dirs.clone()),
+            (
+                "DIRS_PATH",
+                env::join_paths(iter::once(dirs).chain(shared))
+                    .map(|paths| paths.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ),
+            ("HIST", hist.clone()),
+            ("INCR", incr.to_string()),
+            ("MAX_PRIORITY", max_priority.to_string()),
+            ("HIST_TTL", hist_ttl.to_string()),
+            ("HIST_MAX_LINES", hist_max_lines.to_string()),
+            ("PER_SHELL_HISTORY", per_shell_history.to_string()),
+            ("FUZZY_WEIGHT", fuzzy_weight.to_string()),
+            ("FUZZY_THRESHOLD", fuzzy_threshold.to_string()),
+            (
+                "MATCH",
+                tiers
+                    .iter()
+                    .map(|tier| tier.name())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            ("CASE_SENSITIVE", case_sensitive.to_string()),
+            ("EDITOR", editor.clone()),
+            ("CANONICALIZE", canonicalize.to_string()),
+            ("INTERACTIVE", interactive.to_string()),
+            ("NO_CLEAR", no_clear.to_string()),
+            ("OUTPUT_SEP", output_sep.clone()),
+        ]
+    }
+
+    /// Get the shell whose directories are popped.
+    /// # Returns
+    /// The id of the current shell if the history is per shell, or `None` to pop the directories of all the shells (also when the shell has no id).
+    pub fn shell(&self) -> Option<&str> {
+        self.shell_id.as_deref().filter(|_| self.per_shell_history)
+    }
+}
+
+/// Error returned by `parse_args` when the command line can't be read.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    /// An option is unknown (e.g. a typo like `-cdoe`), with the valid ones: it mustn't be read as a shortcut or a path.
+    InvalidOption {
+        /// The valid options, sorted.
+        expected: Vec<String>,
+        /// The unknown option.
+        option: String,
+    },
+    /// The current directory is needed, but can't be accessed.
+    NoCurrentDir(NoCurrentDir),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::InvalidOption { expected, option } => write!(
+                f,
+                "Invalid option <{option}>: expected one of {}",
+                expected.join(", ")
+            ),
+            Self::NoCurrentDir(er) => write!(f, "{er}"),
+        }
+    }
+}
+
+///////////////////////////////: goto functions  :///////////////////////////////
+
+/// Parse the increment of the usage of a folder (see `GlobalData::incr`).
+/// # Arguments
+/// * `value` - The value given by the user
+/// * `name` - The name of the option or variable, for the error
+/// # Returns
+/// The increment, or 10 if the value isn't an integer between 0 and `u32::MAX`.
+pub fn parse_incr(value: &str, name: &str) -> u32 {
+    value.trim().parse::<u32>().unwrap_or_else(|er| {
+        user_error!(
+            "The value of <{name}> must be an integer between 0 and {}: {er}",
+            u32::MAX
+        );
+        10
+    })
+}
+
+/// Function to read an environment variable, ignored if it is blank.
+/// # Arguments
+/// * `name` - The name of the variable, e.g. `GOTO_EDITOR`
+/// # Returns
+/// The value of the variable, or `None` if it is blank or isn't set.
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Function to read a switch from an environment variable.
+/// # Arguments
+/// * `name` - The name of the variable, e.g. `GOTO_NO_CLEAR`
+/// # Returns
+/// `true` if the variable is `1` or `true` (in any case), `false` if it is anything else or isn't set.
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+/// Parse a number read from an environment variable (see `GlobalData`).
+/// # Arguments
+/// * `value` - The value of the variable, or `None` if it isn't set
+/// * `name` - The name of the variable, for the error
+/// * `default` - The number used when the variable isn't set, is empty or is invalid
+/// * `valid` - The condition that the number must meet
+/// * `expected` - The description of the valid numbers, for the error
+/// # Returns
+/// The number, or `default` (with an error if the value isn't a valid number).
+fn parse_number<T>(
+    value: Option<String>,
+    name: &str,
+    default: T,
+    valid: fn(T) -> bool,
+    expected: &str,
+) -> T
+where
+    T: str::FromStr + Copy,
+{
+    let Some(text) = value.filter(|text| !text.trim().is_empty()) else {
+        return default;
+    };
+    match text.trim().parse::<T>() {
+        Ok(number) if valid(number) => number,
+        _ => {
+            user_error!(
+                "The value of <{name}> must be {expected}, not <{}>",
+                text.trim()
+            );
+            default
+        }
+    }
+}
+
+/// Find the folder of the data files (`dirs.csv`, `hist.csv`, ...).
+/// # Arguments
+/// * `unix` - `true` if the OS is unix, `false` if the OS is windows
+/// # Returns
+/// The folder, ending with a `/`:
+/// * `GOTO_DIR`, if set,
+/// * else `$XDG_CONFIG_HOME/goto/` (`~/.config/goto/` if not set) on unix, and `%APPDATA%/goto/` on windows,
+/// * else the `lib/` folder next to the folder of the executable (e.g. `goto/lib/` for `goto/release/goto`).
+/// # Note
+/// The folder is created by `GlobalData::create_files` if it doesn't exist.
+fn data_folder(unix: bool) -> String {
+    let mut folder = env_value("GOTO_DIR")
+        .or_else(|| {
+            if unix {
+                env_value("XDG_CONFIG_HOME")
+                    .or_else(|| env_value("HOME").map(|home| format!("{home}/.config")))
+                    .map(|config| format!("{config}/goto"))
+            } else {
+                env_value("APPDATA").map(|appdata| format!("{appdata}/goto"))
+            }
+        })
+        .or_else(|| {
+            env::current_exe()
+                .ok()
+                .as_deref()
+                .and_then(path::Path::parent)
+                .and_then(path::Path::parent)
+                .map(|root| root.join("lib").to_string_lossy().into_owned())
+        })
+        .system_error("Unable to find a folder for the data files: set GOTO_DIR");
+    if !folder.ends_with(['/', '\\']) {
+        folder.push('/');
+    }
+    folder
+}
+
+/// Get the arguments of the command.
+/// # Arguments
+/// * `gdata` - The static data of the program (number of arguments and aliases of each supported command)
+/// * `args` - The arguments of the command, without the name of the program (e.g. `env::args().skip(1)`)
+/// # Returns
+/// A tuple containing the arguments of the command, and the arguments of the command that are not part of the command.
+/// # Errors
+/// If an unknown option is given, or if a relative path (or the current directory as a default value) is given, but the current directory can't be accessed (e.g. if it was deleted).
+/// # Note
+/// This function is used to get the arguments of the command, and to separate the arguments of the command that are not part of the command.
+/// The function also checks that the number of arguments of the command is valid.
+/// The function is called at the beginning of the program, to get the arguments of the command, and to separate the arguments of the command that are not part of the command.
+/// The function is also used to check that the number of arguments of the command is valid.
+/// The function is also used to separate the arguments of the command that are not part of the command.
+/// The `no_dirs` options are collected wherever they are, so that `. gt foo -code` and `. gt -code foo` are the same: only the values that follow an option that takes some (see `nargcs`) are given to it.
+///
+//
+pub fn parse_args(
+    gdata: &GlobalData,
+    args: &[String],
+) -> Result<(Vec<Cmd>, Vec<Opt>, bool), ArgsError> {
+    let mut cmdline = args.iter();
+    let mut args1: Vec<Cmd> = vec![];
+    let mut args2: Vec<Opt> = vec![];
+    let mut get = false;
+    // `true` if the last option pushed is a `no_dirs` option
+    let mut last_opt = false;
+    // `None` if the current directory was deleted: only an error if it is needed as a default value, or for a relative path
+    let here = commands::current_dir();
+    let mut result = Ok(());
+
+    loop {
+        let temp = cmdline.next();
+        match temp {
+            None => break,
+            Some(arg) => {
+                let curr = (*gdata.aliass.get(arg.as_str()).unwrap_or(&arg.as_str())).to_owned();
+                let opt_full = args2.last().is_none_or(|opt| {
+                    opt.values.len() >= *gdata.nargcs.get(opt.name.as_str()).unwrap_or(&0)
+                });
+
+                match gdata.argcs.get(curr.as_str()) {
+                    Some(value) => {
+                        if cmdline.len() < *value {
+                            result = result
+                                .and_then(|()| args1.last_mut().append_default(here.as_deref()));
+                        }
+                        args1.push(curr.to_cmd());
+                        last_opt = false;
+                    }
+
+                    None => match curr.as_str() {
+                        "features" => break,
+                        "-get" => {
+                            get = true;
+                            args1.push(curr.to_cmd());
+                            last_opt = false;
+                        }
+                        // Is a no_dirs command (code, clear, still, pop, state, noclear, etc.)
+                        _ if gdata.no_dirs.contains(&curr.as_str()) => {
+                            args2.push(Opt {
+                                name: curr.clone(),
+                                values: vec![],
+                            });
+                            last_opt = true;
+                        }
+                        // Is an argument to a previous no_dirs option
+                        _ if last_opt && !opt_full => {
+                            if let Some(opt) = args2.last_mut() {
+                                opt.values.push(curr.clone());
+                            }
+                        }
+                        // A typo in an option mustn't be read as a shortcut or a path
+                        _ if curr.starts_with('-') && !gdata.flags.contains(&curr.as_str()) => {
+                            return Err(invalid_option(gdata, curr));
+                        }
+                        // Is an argument to a previous option
+                        _ => match args1.last_mut() {
+                            None => args1.push(Cmd::Get(ShortPath {
+                                short: Some(curr.clone()),
+                                path: None,
+                            })),
+                            Some(last) => result = result.and_then(|()| last.append(curr)),
+                        },
+                    },
+                }
+            }
+        }
+    }
+
+    result = result.and_then(|()| args1.last_mut().append_default(here.as_deref()));
+    if args1.is_empty() {
+        args1.push(Cmd::default());
+    }
+
+    result
+        .map(|()| (args1, args2, get))
+        .map_err(ArgsError::NoCurrentDir)
+}
+
+/// Build the error of an unknown option, with the valid ones.
+/// # Arguments
+/// * `gdata` - The static data of the program, for the valid options
+/// * `option` - The unknown option
+/// # Returns
+/// The error, to be printed by the caller.
+fn invalid_option(gdata: &GlobalData, option: String) -> ArgsError {
+    let mut expected = gdata
+        .argcs
+        .keys()
+        .chain(gdata.aliass.keys())
+        .chain(gdata.no_dirs.iter())
+        .chain(gdata.flags.iter())
+        .chain(iter::once(&"-get"))
+        .map(|valid| (*valid).to_owned())
+        .collect::<Vec<_>>();
+    expected.sort_unstable();
+    expected.dedup();
+    ArgsError::InvalidOption { expected, option }
+}
+
+/// Convers path to unix or dos, depending on the OS.
+/// # Arguments
+/// * `ipath` - A path in DOS or UNIX format
+/// * `unix` - `true` if the path should be converted to UNIX format, `false` if the path should be converted to DOS format
+/// # Returns
+/// The path in DOS or UNIX format, depending on the OS.
+/// # Note
+/// Network paths (see `commands::is_unc`) are converted by `unc2os`.
+/// Drive-relative paths (e.g. `C:foo`, relative to the current directory of the drive, which is unknown) are read from the root of the drive, with an error.
+/// The line ending of a path read from a file (`\r\n`, or a lone `\r` if the file was edited on windows) is removed.
+/// # Example
+/// ```
+/// use goto::dos2unix;
+/// assert!(dos2unix(String::from("D:\\My Documents\\thing"), true) == "/mnt/d/My Documents/thing");
+/// assert!(dos2unix(String::from("/mnt/d/My Documents/thing"), false) == "d:/My Documents/thing");
+/// assert!(dos2unix(String::from("/mnt/c"), false) == "c:/");
+/// ```
+pub fn dos2unix(mut path: String, unix: bool) -> String {
+    path.truncate(path.trim_end_matches(['\r', '\n']).len());
+    let chars: Vec<char> = path.chars().collect();
+    if commands::is_unc(&path) {
+        unc2os(&path, unix)
+    } else if chars.get(1) == Some(&':') {
+        let drive = chars.first().unwrap_or(&'c');
+        let rest: String = chars.get(2..).unwrap_or_default().iter().collect();
+        if !rest.is_empty() && !rest.starts_with(['/', '\\']) {
+            user_error!("{path} is relative to the current directory of the drive {drive}:, which is unknown: it is read from the root of the drive");
+        }
+        let from_root = rest.trim_start_matches(['/', '\\']).replace('\\', "/");
+        if unix {
+            // WSL mounts the drives in lowercase (e.g. `/mnt/d/`)
+            format!("/mnt/{}/{from_root}", drive.to_ascii_lowercase())
+        } else {
+            format!("{drive}:/{from_root}")
+        }
+    } else if path.starts_with("/mnt/")
+        && !unix
+        && chars.get(5).is_some_and(char::is_ascii_alphabetic)
+        && chars.get(6).is_none_or(|chr| *chr == '/')
+    {
+        let rest: String = chars.get(7..).unwrap_or_default().iter().collect();
+        format!("{}:/{rest}", chars.get(5).unwrap_or(&'c'))
+    } else {
+        path
+    }
+}
+
+/// Converts a network path (see `commands::is_unc`) to the format of the OS.
+/// # Arguments
+/// * `path` - A network path, with `/` or `\` as separators
+/// * `unix` - `true` if the path should be converted to UNIX format, `false` if the path should be converted to DOS format
+/// # Returns
+/// The path in DOS format (e.g. `\\server\share\dir`) or in UNIX format (e.g. `//server/share/dir`).
+/// # Note
+/// On unix, the paths to a WSL distribution (e.g. `\\wsl$\Ubuntu\home` or `\\wsl.localhost\Ubuntu\home`) are paths of the distribution itself (e.g. `/home`).
+fn unc2os(path: &str, unix: bool) -> String {
+    let parts = path
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    match parts.as_slice() {
+        [server, _, rest @ ..]
+            if unix
+                && (server.eq_ignore_ascii_case("wsl$")
+                    || server.eq_ignore_ascii_case("wsl.localhost")) =>
+        {
+            format!("/{}", rest.join("/"))
+        }
+        _ if unix => format!("//{}", parts.join("/")),
+        _ => format!("\\\\{}", parts.join("\\")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Function to parse the arguments, with the displayed commands and the names of the options.
+    fn parse(args: &[&str]) -> (Vec<String>, Vec<String>, bool) {
+        let values = args
+            .iter()
+            .map(|arg| String::from(*arg))
+            .collect::<Vec<_>>();
+        let (args1, args2, get) = parse_args(&GlobalData::default(), &values).unwrap_or_default();
+        (
+            args1.iter().map(ToString::to_string).collect(),
+            args2
+                .iter()
+                .map(|opt| {
+                    iter::once(&opt.name)
+                        .chain(&opt.values)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect(),
+            get,
+        )
+    }
+
+    #[test]
+    fn dos2unix_of_drives() {
+        assert_eq!(dos2unix(String::from("C:\\"), true), "/mnt/c/");
+        assert_eq!(
+            dos2unix(String::from("C:\\Users\\me"), true),
+            "/mnt/c/Users/me"
+        );
+        assert_eq!(dos2unix(String::from("C:"), true), "/mnt/c/");
+        assert_eq!(dos2unix(String::from("C:foo"), true), "/mnt/c/foo");
+        assert_eq!(dos2unix(String::from("C:\\"), false), "C:/");
+        assert_eq!(
+            dos2unix(String::from("/mnt/c/Users/me"), false),
+            "c:/Users/me"
+        );
+        assert_eq!(dos2unix(String::from("/mnt/c"), false), "c:/");
+        assert_eq!(
+            dos2unix(String::from("/mnt/c/Users/me"), true),
+            "/mnt/c/Users/me"
+        );
+        assert_eq!(dos2unix(String::from("/mnt/code"), false), "/mnt/code");
+    }
+
+    #[test]
+    fn dos2unix_round_trip() {
+        for path in ["c:/", "c:/Users/me", "d:/My Documents/thing"] {
+            let unix = dos2unix(String::from(path), true);
+            assert_eq!(dos2unix(unix, false), path);
+        }
+    }
+
+    #[test]
+    fn dos2unix_of_network_paths() {
+        assert_eq!(
+            dos2unix(String::from("\\\\wsl$\\Ubuntu\\home\\me"), true),
+            "/home/me"
+        );
+        assert_eq!(
+            dos2unix(String::from("\\\\wsl.localhost\\Ubuntu\\home"), true),
+            "/home"
+        );
+        assert_eq!(
+            dos2unix(String::from("\\\\server\\share\\dir"), true),
+            "//server/share/dir"
+        );
+        assert_eq!(
+            dos2unix(String::from("//server/share/dir"), false),
+            "\\\\server\\share\\dir"
+        );
+    }
+
+    #[test]
+    fn dos2unix_of_line_endings() {
+        assert_eq!(dos2unix(String::from("/home/me\r\n"), true), "/home/me");
+        assert_eq!(dos2unix(String::from("/home/me\r"), true), "/home/me");
+        assert_eq!(dos2unix(String::from("/home/me\n"), true), "/home/me");
+        assert_eq!(
+            dos2unix(String::from("/home/me\r\n\r\n\n"), true),
+            "/home/me"
+        );
+        assert_eq!(
+            dos2unix(String::from("C:\\Users\\me\r\n"), true),
+            "/mnt/c/Users/me"
+        );
+        assert_eq!(
+            dos2unix(String::from("/mnt/c/Users/me\r"), false),
+            "c:/Users/me"
+        );
+        assert_eq!(
+            dos2unix(String::from("C:\\Users/me\\docs"), true),
+            "/mnt/c/Users/me/docs"
+        );
+    }
+
+    /// Function to parse the value of `GOTO_FUZZY_WEIGHT`.
+    fn fuzzy_weight(value: Option<&str>) -> f64 {
+        parse_number(
+            value.map(String::from),
+            "GOTO_FUZZY_WEIGHT",
+            0.5,
+            |weight: f64| weight.is_finite() && weight.is_sign_positive(),
+            "a positive number",
+        )
+    }
+
+    #[test]
+    fn parse_number_of_the_fuzzy_weight() {
+        assert!((fuzzy_weight(Some(" 2.5 ")) - 2.5).abs() < f64::EPSILON);
+        assert!(fuzzy_weight(Some("0")).abs() < f64::EPSILON);
+        for invalid in [
+            None,
+            Some(""),
+            Some("-1"),
+            Some("NaN"),
+            Some("inf"),
+            Some("heavy"),
+        ] {
+            assert!((fuzzy_weight(invalid) - 0.5).abs() < f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn parse_number_of_the_maximum_priority() {
+        let max_priority = |value: &str| {
+            parse_number(
+                Some(String::from(value)),
+                "GOTO_MAX_PRIORITY",
+                1_000_000,
+                |max: u32| max > 0,
+                "an integer between 1 and 4294967295",
+            )
+        };
+        assert_eq!(max_priority("500"), 500);
+        assert_eq!(max_priority("0"), 1_000_000);
+        assert_eq!(max_priority("-5"), 1_000_000);
+        assert_eq!(max_priority("4294967296"), 1_000_000);
+    }
+
+    #[test]
+    fn settings_of_the_paths() {
+        let gdata = GlobalData {
+            dirs: String::from("/data/dirs.csv"),
+            shared: vec![String::from("/shared/dirs.csv")],
+            folder: String::from("/data/"),
+            ..GlobalData::default()
+        };
+        let settings = gdata.settings();
+        let setting = |name: &str| {
+            settings
+                .iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(setting("DIR"), Some("/data/"));
+        assert_eq!(setting("DIRS"), Some("/data/dirs.csv"));
+        assert!(setting("DIRS_PATH").is_some_and(|paths| paths.ends_with("/shared/dirs.csv")));
+        assert!(setting("FUZZY_WEIGHT").is_some());
+        assert!(setting("OUTPUT_SEP").is_some());
+        assert_eq!(setting("SHELL_ID"), None);
+    }
+
+    #[test]
+    fn parse_args_of_a_shortcut() {
+        assert_eq!(parse(&[]), (vec![String::from("<goto  >")], vec![], false));
+        assert_eq!(
+            parse(&["f"]),
+            (vec![String::from("<goto f >")], vec![], false)
+        );
+        assert_eq!(
+            parse(&["-get", "f"]),
+            (vec![String::from("<goto f >")], vec![], true)
+        );
+    }
+
+    #[test]
+    fn parse_args_of_the_options_anywhere() {
+        let expected = (
+            vec![String::from("<goto f >")],
+            vec![String::from("-code"), String::from("-pop 2")],
+            false,
+        );
+        assert_eq!(parse(&["-code", "f", "-pop", "2"]), expected);
+        assert_eq!(parse(&["f", "-code", "-pop", "2"]), expected);
+    }
+
+    #[test]
+    fn parse_args_of_the_aliases() {
+        assert_eq!(parse(&["-a", "f", "/home/user"]).0, ["<add f /home/user>"]);
+        assert_eq!(parse(&["-rm", "f"]).0, ["<rm f>"]);
+        assert_eq!(parse(&["!", "f"]).1, ["-noclear"]);
+    }
+
+    #[test]
+    fn parse_args_rejects_an_unknown_option() {
+        let values = [String::from("-cdoe"), String::from("f")];
+        let parsed = parse_args(&GlobalData::default(), &values);
+        assert!(
+            matches!(&parsed, Err(ArgsError::InvalidOption { option, .. }) if option == "-cdoe")
+        );
+        assert!(parsed.is_err_and(|er| er.to_string().contains("-code")));
+    }
+}
@@ -5,43 +5,186 @@
     clippy::nursery,
     clippy::cargo
 )]
-#![feature(stmt_expr_attributes)]
-#![allow(clippy::implicit_return, clippy::single_call_fn)]
-#![allow(clippy::string_add)]
-#![allow(clippy::pattern_type_mismatch)]
+#![allow(
+    clippy::blanket_clippy_restriction_lints,
+    reason = "the restriction lints are enabled together, and the ones that don't fit are allowed below"
+)]
+#![allow(
+    clippy::implicit_return,
+    clippy::single_call_fn,
+    reason = "functions end with their value, and are split for readability"
+)]
+#![allow(clippy::string_add, reason = "strings are built with `+`")]
+#![allow(
+    clippy::pattern_type_mismatch,
+    reason = "references are matched with default binding modes"
+)]
 ///////////////////////////////: Documentation  :///////////////////////////////
 
 //! `goto` is a command line tool to navigate through directories.
 //! # Arguments
 //! * None: Go to the directory associated with the shortcut.
-//!     - Usage: `. gt [shortcut]`.
-//!     - Note: If no shortcut is given, you will travel to the most used directory.
+//!     - Usage: `. gt [shortcut] [subdirectory]`.
+//!     - Note: The options that don't modify the list (e.g. `-code` or `-still`) can be given before or after the shortcut: `. gt foo -code` is the same as `. gt -code foo`.
+//!     - Note: An unknown option (e.g. a typo like `-cdoe`) is an error, with the list of the valid ones: nothing is run, instead of reading it as a shortcut or a path.
+//!     - Note: The subdirectory is joined to the directory of the shortcut (e.g. `. gt proj src/module`). If it doesn't exist, you will travel to the directory of the shortcut.
+//!     - Note: If no shortcut is given, you will travel to the most used directory. If the shortcut is not found by any strategy, an error is printed and the directory isn't changed (the most used directory is never used for a given shortcut).
+//!     - Note: The most used directory is found by frecency: the priority of a directory counts for half as much every week since it was last used.
+//!     - Note: Once a priority reaches 1000000, all the priorities are halved, so that their order is kept.
+//!     - Note: If the shortcut is not found, the strategies listed in the `GOTO_MATCH` environment variable are tried in order (`exact,fuzzy` by default):
+//!         - `exact`: a shortcut of the directory is the given shortcut (always tried first),
+//!         - `prefix`: a shortcut of the directory starts with the given shortcut (the most used directory wins),
+//!         - `fuzzy`: a shortcut of the directory contains the letters of the given shortcut in order, or is a few typos away from it (the closest and most used directory wins),
+//!         - `basename`: the name of the directory is the given shortcut.
+//!     - Example: `export GOTO_MATCH=exact,prefix,fuzzy`.
+//!     - Note: The shortcuts are case-insensitive when no shortcut has the exact case (also for `-remove`), unless `GOTO_CASE_SENSITIVE` is set to `1` or `true`.
+//!     - Note: If `GOTO_INTERACTIVE` is set to `1` or `true` and several shortcuts start with the given one, the directory is chosen in a numbered menu (only in a terminal).
 //! * `-add` (or `-a`) - Add a directory to the list of supported directories.
-//!     - Usage: `. gt -add [shortcut] [path]`.
+//!     - Usage: `. gt -add [shortcut]... [path] [--after shortcut] [--allow-missing] [--to file] [--global|--local]`.
 //!     - Note: If path is not given, the current directory is used. If shortcut is not given, the name of the current folder is used.
+//!     - Note: If the path doesn't exist, nothing is added, unless `--allow-missing` is given (e.g. for a directory that will be created later).
+//!     - Note: If the path is a file, its directory is added instead, also with `-edit` (e.g. `. gt -add cfg ~/.config/app/config.toml` adds `~/.config/app`).
+//!     - Note: With several values, the last one is the path (e.g. `. gt -add s1 s2 s3 /path`). The shortcuts that already exist are not added.
+//!     - Note: The shortcuts that start with `-`, are commands or aliases (e.g. `?`), or contain `;`, `/`, `\` or a newline are not added either, also with `-clone` and `-replace-shortcut`.
+//!     - Note: If the path is already in the list, the shortcuts are added to its line (with its priority) instead of a new line.
+//!     - Note: The new line is added at the end of the list, or right after the line of the shortcut given with `--after`.
+//!     - Note: The directory is added to the personal list, or to the file given with `--to` (e.g. a shared list, see `GOTO_DIRS_PATH`).
+//!     - Note: With `--global`, the directory is added to the first shared list (see `GOTO_DIRS_PATH`), also with `-edit`. `--local`, the default, uses the personal list.
+//!     - Note: A leading `~` and the environment variables (`$VAR`, or `%VAR%` on Windows) of the path are expanded, also with `-edit` (e.g. `. gt -add proj '~/projects/foo'`).
+//!     - Note: If the path exists, its symbolic links are resolved, also with `-edit`, unless `GOTO_CANONICALIZE` is set to `0` or `false`.
 //! * `-remove` (or `-rm`) - Remove a directory from the list of supported directories.
-//!     - Usage: `. gt -remove [shortcut]`.
-//!     - Panics: If no shortcut is given.
-//! * `-delete` (or `-del`) - Remove a shortcut from the list of supported shortcuts.
-//!     - Usage: `. gt -delete [path]`.
-//!     - Panics: If no path is given.
+//!     - Usage: `. gt -remove [shortcut]...`.
+//!     - Note: If no shortcut is given (or with `-rm -i` or `-interactive-remove`), the shortcuts are listed with a number, and the ones whose numbers are typed are removed.
+//! * `-delete` (or `-del`) - Remove a directory, with all its shortcuts, from the list of supported shortcuts.
+//!     - Usage: `. gt -delete [path|shortcut]`.
+//!     - Note: The line removed is the first one whose path or one of whose shortcuts is the given value, e.g. `. gt -delete proj` removes the line of the shortcut `proj`.
 //! * `-edit` (or `-e`) - Edit the path of a directory associated with a shortcut.
-//!     - Usage: `. gt -edit [shortcut] [new path]`.
+//!     - Usage: `. gt -edit [shortcut] [new path] [--global|--local]`.
 //!     - Note: If no new path is given, the current directory is used. If no shortcut is given, the name of the current folder is used.
-//! * `-reset` - Reset the usage of all the directories in the list of supported directories (all set to 0, ⚠️no confirmation and no backup⚠️).
-//! * `-decrement` (or `-decr`) - Decrement the usage of a directory in the list of supported directories.
-//!     - Usage: `. gt -decrement [int]`.
+//! * `-clone` - Add a new line with the path of a shortcut, under a new shortcut, with its own usage (starting at 0).
+//!     - Usage: `. gt -clone [shortcut] [new shortcut]`.
+//!     - Note: The two shortcuts then have independent usages, unlike when using `-add` with an existing path.
+//! * `-replace-shortcut` (or `-rename` or `-mv`) - Rename a shortcut, wherever it is in the list of supported directories (the priority is kept).
+//!     - Usage: `. gt -replace-shortcut [shortcut] [new shortcut]`.
+//!     - Note: Nothing is changed if the shortcut doesn't exist, or if the new shortcut already exists.
+//! * `-move` (or `-mvpath`) - Change the path of a directory, keeping its shortcuts and its priority (e.g. after moving the folder).
+//!     - Usage: `. gt -move [path] [new path]`.
+//!     - Note: If the new path is already in the list, the shortcuts of the old path are added to it, and the highest priority is kept.
+//! * `-prune` (or `-gc`) - Remove the directories that don't exist anymore from the list of supported directories.
+//!     - Usage: `. gt -prune [--broken] [--dry-run]`.
+//!     - Note: With `--broken`, only the directories marked with `✗` in `-state` are removed.
+//!     - Note: The removed directories and their number are printed. With `--dry-run`, they are only printed, and the list is not modified.
+//! * `-set` - Set the priority of the directory of a shortcut.
+//!     - Usage: `. gt -set [shortcut] [int]`.
+//! * `-freeze-priority` - Stop incrementing the priority of a directory when its shortcut is used.
+//!     - Usage: `. gt -freeze-priority [shortcut]`.
+//! * `-unfreeze-priority` - Increment again the priority of a directory when its shortcut is used.
+//!     - Usage: `. gt -unfreeze-priority [shortcut]`.
+//! * `-reset` - Reset the usage of all the directories in the list of supported directories (all set to 0).
+//!     - Note: A confirmation is asked (unless `-force` is given), and the list is first saved in `dirs.csv.bak`.
+//! * `-decrement` (or `-decr`) - Decrement the usage of all the directories in the list of supported directories, or only of the directory of a shortcut.
+//!     - Usage: `. gt -decrement [shortcut] [int]`.
+//!     - Note: The values are read by position: with two values, the first one is the shortcut, even if it is a number (e.g. `. gt -decrement 0 5`).
 //!     - Panics: If no decrementation level is given.
+//! * `-age` - Multiply the usage of all the directories in the list of supported directories by a factor, rounding down, so that the directories not used anymore sink.
+//!     - Usage: `. gt -age [factor]`: `factor` is between 0 and 1 (0.9 by default).
+//!     - Note: Run it periodically (e.g. `goto -age` in a cron job) to keep the priorities fresh.
 //! * `-pop` (or `-p` or `<`) - Pop the last directory from the history of directories (only works if you use only `goto` to navigate through directories).
+//!     - Usage: `. gt -pop [N]`: pops `N` directories at once (1 by default).
+//!     - Note: If a directory was saved with `-temp`, it is popped instead.
+//!     - Note: If the history has less than `N` older directories, the oldest one is used, and the number of directories popped is printed.
+//!     - Note: If `GOTO_PER_SHELL_HISTORY` is set to `1` or `true`, only the directories of the current shell are popped. The shell is identified by `GOTO_SHELL_ID`, set by the wrappers (`gt`, `gt.ps1` and `-init nu`) to the pid of the shell: without it, the directories of all the shells are popped.
+//! * `-forward` (or `-fwd`) - Go back to the last directory popped with `-pop`.
+//!     - Note: The popped directories are forgotten as soon as you navigate to another directory (without `-pop` or `-forward`).
+//! * `-history` (or `-hist`) - Print the history of directories, from the most recent one, with the time since they were visited.
+//!     - Note: The directories that don't exist anymore are not printed.
+//! * `-clean-history` - Remove the directories that don't exist anymore from the history, and keep only the most recent visit of each directory.
+//!     - Note: The number of lines removed is printed.
+//! * `-temp` - Go to the directory associated with the shortcut, and save the current directory to come straight back to it.
+//!     - Usage: `. gt -temp [shortcut]`.
+//!     - Note: The next `-pop` or `-return` goes back to the saved directory, whatever the navigation in between. Only one directory is saved.
+//! * `-return` - Go back to the directory saved by `-temp`.
 //! * `-state` (or `?`) - Print the state of the list of supported directories.
 //!     - Note: The state is printed in the following format: `path shortcut1 shortcut2 ... priority_level\n...`.
+//!     - Note: The directories that didn't exist when last used are marked with `✗`.
+//!     - Note: The paths are printed as stored, i.e. with their semicolons escaped as `\;` (and their backslashes as `\\`).
+//!     - Usage: `. gt -state [--by-mtime|--sorted]`: with `--by-mtime`, the directories are sorted by their last modification on the disk, and with `--sorted`, by priority (highest first).
+//!     - Note: In a terminal, the paths that don't exist are printed in red, and the directory with the highest priority in green.
+//!     - Note: The terminal is not cleared, so the output can be piped (e.g. `. gt -state | less`).
+//!     - Note: The other commands are still run after printing the state (e.g. `. gt -state -code foo`).
 //! * `-noclear` (or `-nc` or `!`) - The terminal is cleared by default after the command. Use this argument to avoid clearing the terminal.
-//! * `-code` (or `-c`) - Open the directory in Visual Studio Code.
+//!     - Note: Set `GOTO_NO_CLEAR` to `1` or `true` to never clear the terminal. It isn't cleared either when stderr isn't a terminal.
+//! * `-code` (or `-c`) - Open the directory in the editor given by the `GOTO_EDITOR` environment variable (`code` by default, for Visual Studio Code).
 //! * `-still` (or `%`) - Don't change directory after the command (useful with `-code`).
+//! * `--weight` - Increment the priority of the directory by another value than `GOTO_INCR` (10 by default), only for this call.
+//!     - Usage: `. gt [shortcut] --weight [int]`, e.g. with `0` in a script that visits many directories, so that it doesn't change their priorities.
 //! * `-get` (or `-g`) - Get the path of the directory to go to (implies `-still` and `-noclear`).
 //!    - Usage: `. gt -get [shortcut]`.
 //!    - Note: If no shortcut is given, you will get the path of the most used directory.
-//! * `-clear` (or `-cls`) - Totally errases the list of supported directories (⚠️no confirmation and no backup⚠️).
+//! * `-raw` - Print only the path of the directory, followed by a newline, for the scripts (implies `-still` and `-noclear`).
+//!    - Usage: `cd "$(goto -get -raw [shortcut])"`.
+//!    - Note: If no directory is found, nothing is printed and the exit code is 1.
+//! * `-rank` - Print the shortcuts sorted by priority, with their rank number.
+//!     - Usage: `. gt -rank [N]`.
+//!     - Note: If `N` is given, only the `N` most used directories are printed. The lines are printed in the following format: `#1 shortcut1 shortcut2 ... (priority_level) path`.
+//! * `-top` - Print the most used directories, with their priority and their shortcuts.
+//!     - Usage: `. gt -top [N]`: prints the `N` most used directories (10 by default). The lines are printed in the following format: `priority_level path shortcut1 shortcut2 ...`.
+//! * `-recent` - Print the most recently used directories, with how long ago they were used and their shortcuts.
+//!     - Usage: `. gt -recent [N]`: prints the `N` most recently used directories (10 by default), from the most recent one.
+//!     - Note: The time of the last use is stored in the list when a shortcut is used. For the directories used before, the time of the history is used instead.
+//! * `-search` - Print the directories whose path or a shortcut contains a term (case-insensitive), like `-state`.
+//!     - Usage: `. gt -search [term]`.
+//!     - Note: In a terminal, the occurrences of the term are highlighted.
+//! * `-verify-shell` - Print the version of the protocol used to communicate with the shell wrapper, and its checksum.
+//!     - Note: The wrapper can compare them with its own, to detect that the binary was upgraded but the wrapper wasn't re-sourced.
+//! * `-help` (or `-h`) - Print the commands, with their aliases and their number of values, or the usage of one of them.
+//!     - Usage: `goto -help [command]`, e.g. `goto -help add`.
+//! * `-version` (or `-V`) - Print the version of `goto`.
+//!     - Usage: `goto -version [-v]`: with `-v`, the OS and the path of the list of supported directories are also printed (e.g. to check where the list is read from).
+//! * `-export-env` - Print the configuration of `goto` as `GOTO_X='value'` lines, suitable for sourcing.
+//! * `-config` - Print the files that are used (the list of supported directories, the history and the shared lists), whether they exist and are writable, the priority increment and the OS.
+//!     - Note: Use it to check which `dirs.csv` is read, as it depends on `GOTO_DIR`, `GOTO_DIRS`, `GOTO_DIRS_PATH` and the location of the executable.
+//! * `-init` - Print the wrapper of a shell that can't source the `gt` script, to save and call instead, or the `gt` function of a posix shell.
+//!     - Usage: `goto -init [cmd|nu|bash|zsh|fish]`.
+//!     - Note: For `cmd`, save the wrapper with `goto.exe -init cmd > %USERPROFILE%\gt.bat`, and add `doskey gt=%USERPROFILE%\gt.bat $*` to the `AutoRun` script of `cmd`.
+//!     - Note: For `nu`, save the wrapper with `goto -init nu | save -f ~/.config/nushell/gt.nu`, and add `source ~/.config/nushell/gt.nu` to `config.nu`. Then use `gt` without `.`.
+//!     - Note: For `bash`, add `eval "$(goto -init bash)"` to `~/.bashrc`, to define the `gt` function instead of sourcing the `gt` script. Then use `gt` without `.`.
+//!     - Note: For `zsh`, add `eval "$(goto -init zsh)"` to `~/.zshrc` in the same way.
+//!     - Note: For `fish`, add `goto -init fish | source` to `~/.config/fish/config.fish`.
+//! * `-completions` - Print the script to complete the options and the shortcuts of `g`, `gt` and `goto` in a shell.
+//!     - Usage: `. gt -completions [bash|zsh|fish]`, e.g. `eval "$(goto -completions bash)"` in `~/.bashrc`.
+//!     - Note: The shortcuts are listed by the script when completing (with `goto -shortcuts`), so they are always up to date.
+//! * `-shortcuts` - Print all the shortcuts, one per line, without their paths nor their priorities (e.g. for the completion scripts).
+//!     - Usage: `goto -shortcuts`.
+//! * `-stats` - Print the number of shortcuts and of paths, the total, average and highest priorities, and the number of paths that don't exist anymore.
+//! * `-stats --unused` - Print the directories that were never navigated to (i.e. not in the history), like `-state`.
+//! * `-sweep` - Clean the list of supported directories: normalize the paths, merge the lines with the same path, remove the directories that don't exist anymore and the blank lines, and compact the priorities.
+//!     - Usage: `. gt -sweep [--dry-run]`: with `--dry-run`, the changes are only counted, and the list is not modified.
+//!     - Note: The number of lines changed by each step is printed.
+//! * `-doctor` - Check the list of supported directories: print the malformed lines, the shortcuts used more than once, the paths listed more than once and the directories that don't exist anymore, with their line.
+//!     - Usage: `. gt -doctor [--fix]`: with `--fix`, the malformed lines are removed, and the duplicate shortcuts are removed from all the lines but the first one.
+//!     - Note: The duplicate paths and the missing directories are only printed: use `-sweep` and `-prune` to remove them.
+//! * `-import-zoxide` - Add the directories of a zoxide database to the list of supported directories (zoxide must be installed).
+//!     - Usage: `. gt -import-zoxide [path]`: `path` is the folder of the database (or the database file), if not the default one.
+//!     - Note: The name of each directory is used as shortcut, and its zoxide score as priority. The directories that don't exist, are already in the list, or whose name is already a shortcut are skipped.
+//! * `-export-json` - Export the list of supported directories as JSON, e.g. to sync it across machines.
+//!     - Usage: `. gt -export-json [path]`: the JSON is written to `path`, or printed if no path is given.
+//!     - Note: The JSON contains the version of its format, and for each directory its path, shortcuts, priority and whether it is frozen.
+//! * `-import-json` - Add the directories of a JSON written by `-export-json` to the list of supported directories.
+//!     - Usage: `. gt -import-json [path]`.
+//!     - Note: If a path is already in the list, the shortcuts are added to its line, and the highest priority is kept. The shortcuts that already exist are not added.
+//!     - Note: The malformed directories are skipped with an error, and the numbers of added, merged and skipped directories are printed.
+//!     - Note: A JSON exported by a newer version of `goto` is rejected, and the fields renamed since an older version are read under their old name (e.g. `names` for `shortcuts`).
+//! * `-bulk-edit` - Open the list of supported directories in a text editor (`$VISUAL` or `$EDITOR`).
+//!     - Note: The list is only replaced if all the edited lines are valid, else the first error is printed.
+//! * `-undo` - Restore the list of supported directories as it was before the last command that modified it (e.g. `-add`, `-edit`, `-remove`, `-reset` or `-decrement`).
+//!     - Note: Only the last command can be undone, and the priorities incremented by the navigation since then are restored too.
+//! * `-clear` (or `-cls`) - Totally errases the list of supported directories.
+//!     - Note: A confirmation is asked (unless `-force` is given), and the list is first saved in `dirs.csv.bak`.
+//! * `-force` (or `-y`) - Don't ask for confirmation before `-clear` and `-reset` (useful in scripts).
+//! * `-explain` (or `-why`) - Print how the directory of a shortcut is found, instead of going to it (implies `-still` and `-noclear`).
+//!     - Usage: `. gt -explain [shortcut]`.
+//!     - Note: The strategy that found the directory is printed with its path and priority, followed by the directories found by the other strategies (the runners-up). The priorities are left untouched.
 //! # Examples
 //! In reality, the command will clear the terminal once the command has finished, but the examples are written as if the terminal was not cleared.
 //! ### Add
@@ -71,189 +214,864 @@
 //! ```
 //! ### Code
 //! ```bash
-//! ~ $ . gt -code shortcut1     // Opens vscode in /new/path/to/dir
+//! ~ $ . gt -code shortcut1     // Opens $GOTO_EDITOR (vscode by default) in /new/path/to/dir
 //! /path/to/a/dir $           // Add -still to avoid changing directory
 //! ````
 //! # Note
 //! In `powershell`, you don't need to use the `.` before the command.
-//! Not yet supported by `cmd`.
+//! Quote the paths with spaces, e.g. `. gt -add proj "~/My Projects"`: the wrappers pass each argument to `goto` as is, and change to the path as a whole.
+//! In `cmd`, use the wrapper printed by `goto.exe -init cmd` (see `-init`).
+//!
+//! The data files (`dirs.csv`, `hist.csv`, ...) are stored in `GOTO_DIR` if it is set, else in `$XDG_CONFIG_HOME/goto` (`~/.config/goto` by default) on unix and `%APPDATA%\goto` on windows.
+//! If none of these variables is set, the `lib` folder next to the folder of the executable is used. The folder is created if it doesn't exist.
+//! The list of supported directories and the history can also be set separately with `GOTO_DIRS` and `GOTO_HIST` (e.g. `GOTO_DIRS=./dirs.csv . gt -state`): the files are created empty if they don't exist.
+//! To also use shared lists (e.g. the shortcuts of a team), set `GOTO_DIRS_PATH` to a list of files separated by `:` (`;` on windows), e.g. `$HOME/.config/goto/dirs.csv:/srv/team/dirs.csv`.
+//! The first file is the personal one, used instead of `GOTO_DIRS` and modified by the commands. The others are only searched to navigate, when the personal one has no exact match, and are never modified (except with `-add --to`, or `--global` to modify the first of them).
+//!
+//! The errors are printed in red only if stderr is a terminal: set `NO_COLOR` to never colour them, or `CLICOLOR_FORCE` to always colour them.
+//!
+//! The wrappers read the last line printed by `goto`, whose fields are separated by the unit separator (`\x1f`), as it can't be in a path. Set `GOTO_OUTPUT_SEP` to use another separator: it is read by both `goto` and the wrappers.
+//! The path is always the last field, and the wrappers read everything after the second separator, so that a path containing the separator (e.g. `c#proj` with `GOTO_OUTPUT_SEP=#`) isn't cut.
 
 ///////////////////////////////: Imports  :///////////////////////////////
 
-/// This module contains the structure of the options of the goto command that access the file containing the shorcuts. (-get, -add, -edit, ...)
-mod commands;
-/// This module contains the functions to push and pop directories from the history file.
-mod dirs;
-/// This module contains all the error functions avalaible in all the program.
-mod errors;
-/// This module contains the functions to read and write the supported directories, their shortcuts and their usage.
-mod hist;
+use goto::commands::{self, Cmd, HasOpt as _, Opt, ShortPath};
+use goto::errors::{InteractionError as _, WriteError as _};
+use goto::hist::{self, PopError};
+use goto::{command_error, data_error, file_error, general_error, user_error};
+use goto::{dirs, GlobalData};
 
-use errors::WriteError;
+use core::fmt::Write as _;
+use core::iter;
+use std::io::{self, IsTerminal as _};
+use std::{env, fs, process};
 
-use crate::commands::{AppendDefault, Cmd, ShortPath, ToCmd};
-use crate::errors::InteractionError;
+///////////////////////////////: Global static data  :///////////////////////////////
 
-use std::{collections, env, fs, path, process};
+/// Version of the protocol used to communicate with the shell wrappers.
+/// Must be incremented every time the output read by the wrappers changes, and the wrappers of `release` updated (see `protocol`).
+const PROTOCOL_VERSION: u32 = 2;
+/// Format of the output read by the shell wrappers (see `main`), with the default separator (see `GlobalData::output_sep`).
+const PROTOCOL_FORMAT: &str = "still\x1fget\x1fpath";
+/// Usage and description of the commands, printed by `-help` (see the documentation above).
+/// The commands are listed from `argcs` and `no_dirs`: one missing here is still printed, without its usage.
+const HELP: &[(&str, &str, &str)] = &[
+    ("-get", ". gt -get [shortcut]", "Print the path of the directory to go to (implies -still and -noclear)."),
+    ("-add", ". gt -add [shortcut]... [path] [--after shortcut] [--allow-missing] [--to file] [--global|--local]", "Add a directory to the list of supported directories."),
+    ("-remove", ". gt -remove [shortcut]...", "Remove shortcuts from the list of supported directories (chosen in a menu if none is given)."),
+    ("-delete", ". gt -delete [path|shortcut]", "Remove a directory, with all its shortcuts, from the list of supported directories."),
+    ("-edit", ". gt -edit [shortcut] [new path] [--global|--local]", "Edit the path of a directory associated with a shortcut."),
+    ("-clone", ". gt -clone [shortcut] [new shortcut]", "Add a new line with the path of a shortcut, under a new shortcut, with its own usage."),
+    ("-replace-shortcut", ". gt -replace-shortcut [shortcut] [new shortcut]", "Rename a shortcut, wherever it is in the list (the priority is kept)."),
+    ("-move", ". gt -move [path] [new path]", "Change the path of a directory, keeping its shortcuts and its priority."),
+    ("-prune", ". gt -prune [--broken] [--dry-run]", "Remove the directories that don't exist anymore."),
+    ("-set", ". gt -set [shortcut] [int]", "Set the priority of the directory of a shortcut."),
+    ("-freeze-priority", ". gt -freeze-priority [shortcut]", "Stop incrementing the priority of a directory when its shortcut is used."),
+    ("-unfreeze-priority", ". gt -unfreeze-priority [shortcut]", "Increment again the priority of a directory when its shortcut is used."),
+    ("-reset", ". gt -reset", "Reset the priorities of all the directories to 0 (after a confirmation)."),
+    ("-decrement", ". gt -decrement [shortcut] [int]", "Decrement the priority of all the directories, or only of the directory of a shortcut."),
+    ("-age", ". gt -age [factor]", "Multiply the priorities of all the directories by a factor between 0 and 1 (0.9 by default)."),
+    ("-pop", ". gt -pop [N]", "Go back to the previous directory of the history, or `N` directories back."),
+    ("-forward", ". gt -forward", "Go back to the last directory popped with -pop."),
+    ("-history", ". gt -history", "Print the history of directories, from the most recent one."),
+    ("-clean-history", ". gt -clean-history", "Remove the missing and the duplicate directories from the history."),
+    ("-temp", ". gt -temp [shortcut]", "Go to a directory, and save the current one to come back to it with -return."),
+    ("-return", ". gt -return", "Go back to the directory saved by -temp."),
+    ("-state", ". gt -state [--by-mtime|--sorted]", "Print the list of supported directories."),
+    ("-noclear", ". gt -noclear [shortcut]", "Don't clear the terminal after the command."),
+    ("-code", ". gt -code [shortcut]", "Open the directory in GOTO_EDITOR (code by default)."),
+    ("-still", ". gt -still [shortcut]", "Don't change directory after the command."),
+    ("--weight", ". gt [shortcut] --weight [int]", "Increment the priority by another value than GOTO_INCR, only for this call."),
+    ("-raw", "goto -get -raw [shortcut]", "Print only the path of the directory, for the scripts."),
+    ("-explain", ". gt -explain [shortcut]", "Print how the directory of a shortcut is found, instead of going to it."),
+    ("-rank", ". gt -rank [N]", "Print the shortcuts sorted by priority, with their rank number."),
+    ("-top", ". gt -top [N]", "Print the N most used directories (10 by default)."),
+    ("-recent", ". gt -recent [N]", "Print the N most recently used directories (10 by default)."),
+    ("-search", ". gt -search [term]", "Print the directories whose path or a shortcut contains a term."),
+    ("-shortcuts", "goto -shortcuts", "Print all the shortcuts, one per line."),
+    ("-stats", ". gt -stats [--unused]", "Print statistics about the list, or the directories never navigated to with --unused."),
+    ("-sweep", ". gt -sweep [--dry-run]", "Normalize the paths, merge the duplicates, remove the dead directories and compact the priorities."),
+    ("-doctor", ". gt -doctor [--fix]", "Print the problems of the list, and repair the safe ones with --fix."),
+    ("-import-zoxide", ". gt -import-zoxide [path]", "Add the directories of a zoxide database."),
+    ("-export-json", ". gt -export-json [path]", "Export the list as JSON, to path or to stdout."),
+    ("-import-json", ". gt -import-json [path]", "Add the directories of a JSON written by -export-json."),
+    ("-bulk-edit", ". gt -bulk-edit", "Open the list in $VISUAL or $EDITOR."),
+    ("-undo", ". gt -undo", "Restore the list as it was before the last command that modified it."),
+    ("-clear", ". gt -clear", "Remove all the directories of the list (after a confirmation)."),
+    ("-force", ". gt -force [-clear|-reset]", "Don't ask for confirmation before -clear and -reset."),
+    ("-verify-shell", "goto -verify-shell", "Print the version of the protocol used by the shell wrappers, and its checksum."),
+    ("-version", "goto -version [-v]", "Print the version of goto (and the OS and the path of the list with -v)."),
+    ("-export-env", "goto -export-env", "Print the configuration as GOTO_X='value' lines."),
+    ("-config", "goto -config", "Print the files used, whether they exist and are writable, the priority increment and the OS."),
+    ("-completions", "goto -completions [bash|zsh|fish]", "Print the completion script of a shell."),
+    ("-init", "goto -init [cmd|nu|bash|zsh|fish]", "Print the wrapper of a shell that can't source the gt script, or the gt function of bash, zsh or fish."),
+    ("-help", "goto -help [command]", "Print the commands, or the usage of one of them."),
+];
+/// `gt` function printed by `-init bash` (see `init`).
+const INIT_BASH: &str = r#"# The function must be generated again if `goto` changes the format of its output (see `goto -verify-shell`)
+goto_protocol=$(command goto -verify-shell 2>/dev/null)
+if [[ $goto_protocol != 'PROTOCOL' ]]; then
+    printf '%s\n' "gt: this function was generated for the protocol PROTOCOL of goto, which now uses ${goto_protocol:-another one}: run 'goto -init bash' again" >&2
+fi
+unset goto_protocol
+gt() {
+    local res status sep last still get path
+    # The pid of the shell identifies its directories in the history (see `GOTO_PER_SHELL_HISTORY`)
+    res=$(GOTO_SHELL_ID="${GOTO_SHELL_ID:-$$}" command goto "$@")
+    status=$?
+    sep="${GOTO_OUTPUT_SEP:-$'\x1f'}"
+    # The output of the other commands (e.g. `-state`) is printed before the last line
+    last=${res##*$'\n'}
+    if [[ $last == ?"$sep"?"$sep"* ]]; then
+        if [[ $res != "$last" ]]; then
+            printf '%s\n' "${res%$'\n'*}"
+        fi
+        still=${last:0:1}
+        get=${last:$((1 + ${#sep})):1}
+        path=${last:$((2 + 2 * ${#sep}))}
+        if [[ $get == "1" ]]; then
+            printf '%s\n' "$path"
+        elif [[ $still != "1" && -n $path ]]; then
+            cd -- "$path" || return
+        fi
+    elif [[ -n $res ]]; then
+        printf '%s\n' "$res"
+    fi
+    return $status
+}
+"#;
 
-///////////////////////////////: Global static data  :///////////////////////////////
+/// `gt` function printed by `-init fish` (see `init`).
+const INIT_FISH: &str = r#"# The function must be generated again if `goto` changes the format of its output (see `goto -verify-shell`)
+set -l goto_protocol (command goto -verify-shell 2>/dev/null)
+if test "$goto_protocol" != 'PROTOCOL'
+    printf '%s\n' "gt: this function was generated for the protocol PROTOCOL of goto, which now uses $goto_protocol: run 'goto -init fish' again" >&2
+end
+function gt --wraps goto
+    # The pid of the shell identifies its directories in the history (see `GOTO_PER_SHELL_HISTORY`)
+    set -l id $fish_pid
+    set -q GOTO_SHELL_ID; and set id $GOTO_SHELL_ID
+    set -l output (GOTO_SHELL_ID=$id command goto $argv)
+    set -l ret $status
+    set -l sep \x1f
+    set -q GOTO_OUTPUT_SEP; and set sep $GOTO_OUTPUT_SEP
+    test (count $output) -eq 0; and return $ret
+    # The path can contain the separator
+    set -l fields (string split --max 2 -- $sep $output[-1])
+    if test (count $fields) -lt 3; or not contains -- $fields[1] 0 1; or not contains -- $fields[2] 0 1
+        printf '%s\n' $output
+        return $ret
+    end
+    # The output of the other commands (e.g. `-state`) is printed before the last line
+    test (count $output) -gt 1; and printf '%s\n' $output[1..-2]
+    if test $fields[2] = 1
+        printf '%s\n' $fields[3]
+    else if test $fields[1] != 1; and test -n "$fields[3]"
+        cd $fields[3]; or return
+    end
+    return $ret
+end
+"#;
+/// `gt` function printed by `-init zsh` (see `init`).
+const INIT_ZSH: &str = r#"# The function must be generated again if `goto` changes the format of its output (see `goto -verify-shell`)
+goto_protocol=$(command goto -verify-shell 2>/dev/null)
+if [[ $goto_protocol != 'PROTOCOL' ]]; then
+    printf '%s\n' "gt: this function was generated for the protocol PROTOCOL of goto, which now uses ${goto_protocol:-another one}: run 'goto -init zsh' again" >&2
+fi
+unset goto_protocol
+gt() {
+    local res ret sep last dir
+    local -a fields
+    # The pid of the shell identifies its directories in the history (see `GOTO_PER_SHELL_HISTORY`)
+    res=$(GOTO_SHELL_ID="${GOTO_SHELL_ID:-$$}" command goto "$@")
+    ret=$?
+    sep="${GOTO_OUTPUT_SEP:-$'\x1f'}"
+    # The output of the other commands (e.g. `-state`) is printed before the last line
+    last=${res##*$'\n'}
+    fields=("${(@ps:$sep:)last}")
+    if (( ${#fields} >= 3 )) && [[ $fields[1] == [01] && $fields[2] == [01] ]]; then
+        if [[ $res != "$last" ]]; then
+            print -r -- "${res%$'\n'*}"
+        fi
+        # The path can contain the separator
+        dir=${(pj:$sep:)fields[3,-1]}
+        if [[ $fields[2] == 1 ]]; then
+            print -r -- "$dir"
+        elif [[ $fields[1] != 1 && -n $dir ]]; then
+            cd -- "$dir" || return
+        fi
+    elif [[ -n $res ]]; then
+        print -r -- "$res"
+    fi
+    return $ret
+}
+"#;
+
+///////////////////////////////: No dirs functions  :///////////////////////////////
+
+/// Find the path of the directory to go to.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// * `args2` - The `no_dirs` arguments of the command
+/// # Returns
+/// The path of the directory to go to, if the command is valid.
+/// # Warning
+/// If the command is invalid.
+/// # Note
+/// This function is used to find the path of the directory to go to, and to update the usage of the directory if the command is valid.
+/// The function also prints the path of the directory to go to, and calls the `open_editor` function to open the directory in the editor.
+/// The function also calls the `clear` function to clear the terminal, unless the `noclear` argument is present.
+fn no_dirs(gdata: &GlobalData, args2: &[Opt]) -> Option<String> {
+    let mut res = None;
+    let dirs = gdata.dirs.as_str();
+
+    for opt in args2 {
+        match opt.name.as_str() {
+            "-pop" => res = Some(pop(gdata, opt)),
+            "-temp" => match commands::current_dir() {
+                Some(here) => hist::set_return(&gdata.ret, &here),
+                None => user_error!("Unable to access the current directory: nothing to return to"),
+            },
+            "-forward" => {
+                res = Some(
+                    hist::forward(&gdata.hist, &gdata.fwd)
+                        .user_error("No directory to go forward to: use <-pop> first"),
+                );
+            }
+            "-return" => {
+                res = Some(
+                    hist::take_return(&gdata.ret)
+                        .user_error("No directory to return to: use <-temp> first"),
+                );
+            }
+            "-state" | "-rank" | "-top" | "-recent" | "-search" | "-shortcuts" | "-stats" => {
+                list(gdata, opt);
+            }
+            "-history" => hist::history(&gdata.hist),
+            "-clean-history" => hist::clean_history(&gdata.hist),
+            "-verify-shell" => verify_shell(),
+            "-help" => help(gdata, opt.values.first().map(String::as_str)),
+            "-version" => match opt.values.first().map(String::as_str) {
+                None => version(gdata, false),
+                Some("-v") => version(gdata, true),
+                Some(value) => user_error!("Invalid value <{value}> for <-version>: expected -v"),
+            },
+            "-export-env" => export_env(gdata),
+            "-config" => config(gdata),
+            "-completions" => completions(gdata, opt.values.first().map(String::as_str)),
+            "-init" => init(opt.values.first().map(String::as_str)),
+            "-bulk-edit" => dirs::bulk_edit(dirs),
+            "-undo" => dirs::undo(dirs),
+            "-sweep" => match opt.values.first().map(String::as_str) {
+                None => dirs::sweep(dirs, false),
+                Some("--dry-run") => dirs::sweep(dirs, true),
+                Some(value) => user_error!("Invalid value <{value}> for <-sweep>"),
+            },
+            "-doctor" => match opt.values.first().map(String::as_str) {
+                None => dirs::doctor(dirs, false),
+                Some("--fix") => dirs::doctor(dirs, true),
+                Some(value) => user_error!("Invalid value <{value}> for <-doctor>"),
+            },
+            "-import-zoxide" => dirs::import_zoxide(dirs, opt.values.first().map(String::as_str)),
+            "-export-json" => dirs::export_json(dirs, opt.values.first().map(String::as_str)),
+            "-import-json" => match opt.values.first() {
+                Some(json_path) => dirs::import_json(dirs, json_path),
+                None => user_error!("Missing path for <-import-json>"),
+            },
+            "-clear" => {
+                if dirs::confirm(dirs, "remove", args2.has("-force")) {
+                    dirs::clear(dirs);
+                }
+            }
+            "-code" | "-noclear" | "-still" | "-force" | "-explain" | "-raw" | "--weight" => (),
+            _ => user_error!(
+                "Invalid command <{}> in <{}>",
+                opt.name,
+                env::args().collect::<Vec<String>>().join(" ")
+            ),
+        }
+    }
+    res
+}
+
+/// Read the number given to a command, e.g. the number of directories printed by `-top`.
+/// # Arguments
+/// * `opt` - The command and its values
+/// * `default` - The number if none is given
+/// # Returns
+/// The number, or `default` if it isn't an integer (after printing an error).
+fn number_value(opt: &Opt, default: usize) -> usize {
+    opt.values.first().map_or(default, |nb| {
+        nb.parse::<usize>().unwrap_or_else(|er| {
+            user_error!("The value of <{}> must be an integer: {er}", opt.name);
+            default
+        })
+    })
+}
 
-#[derive(Debug)]
-/// Structure to contain all the static data of the program
-struct GlobalData<'global> {
-    /// Path to the file containing the list of shortcuts defined by the user with the `-add` and `-edit` commands.
-    dirs: String,
-    /// Path to the file containing the history for the `-pop` command.
-    hist: String,
-    /// When a folder is used, `incr` is used to increment the usage of the folder
-    incr: u32,
-    /// Gives the number of arguments for each supported command (except the basic `goto` command that can take 0, 1 or 2 arguments).
-    argcs: collections::HashMap<&'global str, usize>,
-    /// `true` if the OS is unix, `false` if the OS is windows
-    unix: bool,
-    /// Gives the alias of every supported command
-    aliass: collections::HashMap<&'global str, &'global str>,
-    /// Gives the arguments that don't require reading `lib/dirs.csv`.
-    no_dirs: &'global [&'global str],
+/// Find the directory to go back to, with `-pop`.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// * `opt` - The `-pop` command, with the number of directories to pop
+/// # Returns
+/// The directory saved by `-temp` if there is one, else the directory popped from the history (empty after printing an error if there is none).
+fn pop(gdata: &GlobalData, opt: &Opt) -> String {
+    hist::take_return(&gdata.ret).unwrap_or_else(|| {
+        hist::popd(&gdata.hist, &gdata.fwd, number_value(opt, 1), gdata.shell()).unwrap_or_else(
+            |er| {
+                match er {
+                    PopError::EmptyHistory => {
+                        user_error!("No directory to go back to: the history is empty");
+                    }
+                    PopError::IoError(io_er) => {
+                        file_error!("Unable to read {}: {io_er}", gdata.hist);
+                    }
+                    PopError::NoValidEntry => {
+                        data_error!("No directory to go back to: the older directories of the history don't exist anymore");
+                    }
+                }
+                String::new()
+            },
+        )
+    })
 }
 
-/// Defining the data for `GlobalData`
-impl<'global> Default for GlobalData<'global> {
-    #[allow(clippy::expect_used)]
-    fn default() -> Self {
-        let mut argcs = collections::HashMap::new();
-        argcs.insert("-add", 2);
-        argcs.insert("-remove", 1);
-        argcs.insert("-delete", 1);
-        argcs.insert("-edit", 2);
-        argcs.insert("-reset", 0);
-        argcs.insert("-del", 1);
-        argcs.insert("-decrement", 1);
-
-        let mut aliass = collections::HashMap::new();
-        aliass.insert("-a", "-add");
-        aliass.insert("-e", "-edit");
-        aliass.insert("-rm", "-remove");
-        aliass.insert("-del", "-delete");
-        aliass.insert("-decr", "-decrement");
-        aliass.insert("-p", "-pop");
-        aliass.insert("<", "-pop");
-        aliass.insert("?", "-state");
-        aliass.insert("-nc", "-noclear");
-        aliass.insert("!", "-noclear");
-        aliass.insert("-c", "-code");
-        aliass.insert("%", "-still");
-        aliass.insert("-g", "-get");
-        aliass.insert("-cls", "-clear");
-
-        let unix = cfg!(target_os = "linux");
-        assert!(unix || cfg!(target_os = "windows"), "Unsupported OS");
-
-        let libfolder = if unix {
-            "/mnt/d/windows/data/goto/"
-        } else {
-            "d:/windows/data/goto/"
+/// Print the directories of the file of supported shortcuts, with the listing commands (`-state`, `-rank`, `-top`, `-recent`, `-search`, `-shortcuts` and `-stats`).
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// * `opt` - The listing command and its values
+fn list(gdata: &GlobalData, opt: &Opt) {
+    let dirs = gdata.dirs.as_str();
+    let value = opt.values.first().map(String::as_str);
+    match opt.name.as_str() {
+        "-state" => {
+            let table = match value {
+                None => dirs::state(dirs, false, false),
+                Some("--by-mtime") => dirs::state(dirs, true, false),
+                Some("--sorted") => dirs::state(dirs, false, true),
+                Some(other) => {
+                    user_error!("Invalid value <{other}> for <-state>");
+                    String::new()
+                }
+            };
+            #[expect(
+                clippy::print_stdout,
+                reason = "the output is read by the user or the shell wrapper"
+            )]
+            {
+                print!("{table}");
+            };
         }
-        .to_owned();
-
-        // let libfolder = if unix {
-        //     format!(
-        //         "{}/.goto/",
-        //         env::var("HOME").system_error("Home variable not found")
-        //     )
-        // } else {
-        //     format!(
-        //         "{}/goto/",
-        //         env::var("APPDATA").system_error("Appdata variable not found")
-        //     )
-        // };
-
-        if !path::Path::new(&libfolder).exists() {
-            fs::create_dir_all(&libfolder).write_error(&libfolder);
+        "-rank" => dirs::rank(
+            dirs,
+            value.and_then(|limit| {
+                limit.parse::<usize>().map_or_else(
+                    |er| {
+                        user_error!("The value of <-rank> must be an integer: {er}");
+                        None
+                    },
+                    Some,
+                )
+            }),
+        ),
+        "-top" => dirs::top(dirs, number_value(opt, 10)),
+        "-recent" => dirs::recent(dirs, &gdata.hist, number_value(opt, 10)),
+        "-search" => match value {
+            Some(term) => dirs::search(dirs, term),
+            None => user_error!("Missing term for <-search>"),
+        },
+        "-shortcuts" => {
+            #[expect(
+                clippy::print_stdout,
+                reason = "the output is read by the user or the shell wrapper"
+            )]
+            {
+                print!("{}", dirs::shortcuts(dirs));
+            };
+            #[expect(
+                clippy::exit,
+                reason = "the process ends with the status of the command"
+            )]
+            process::exit(0);
         }
+        "-stats" => match value {
+            Some("--unused") => dirs::unused(dirs, &gdata.hist),
+            Some(other) => user_error!("Invalid value <{other}> for <-stats>"),
+            None => dirs::stats(dirs),
+        },
+        // Only called with the listing commands (see `no_dirs`)
+        _ => (),
+    }
+}
 
-        // let libfolder = env::current_exe()
-        //     .system_error("Access to current directory forbidden")
-        //     .parent()
-        //     .expect("No parent of main.rs.")
-        //     .parent()
-        //     .expect("No grandparent of main.rs.")
-        //     .join("lib/");
+/// Compute the checksum of a string (32 bits FNV-1a hash).
+/// # Arguments
+/// * `data` - The string to hash
+/// # Returns
+/// The checksum of the string.
+/// # Note
+/// The hash doesn't depend on the version of Rust, so that it can be hardcoded in the shell wrappers.
+fn checksum(data: &str) -> u32 {
+    data.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Get the version of the protocol used by the shell wrappers, and its checksum.
+/// # Returns
+/// The version and the checksum, in the format `version checksum`, e.g. `2 2e4aeb44`.
+/// # Note
+/// It is written in the wrappers (`release/g`, `release/g.ps1`, `release/gtexe.ps1`, and the scripts of `init`), which compare it with the output of `-verify-shell` before reading the output of `goto`.
+fn protocol() -> String {
+    format!(
+        "{PROTOCOL_VERSION} {:08x}",
+        checksum(&format!("{PROTOCOL_VERSION}:{PROTOCOL_FORMAT}"))
+    )
+}
 
-        let dirs = libfolder.clone() + "dirs.csv";
-        let hist = libfolder + "hist.csv";
+/// Print the version of the protocol used by the shell wrappers, and its checksum (see `protocol`).
+/// # Note
+/// The wrapper compares it with the values it was generated with, to detect a binary upgraded without updating the wrapper.
+fn verify_shell() -> ! {
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("{}", protocol());
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-        if !path::Path::new(&dirs).exists() {
-            fs::write(&dirs, "").write_error(&dirs);
+/// Print the commands, or the usage of one of them.
+/// # Arguments
+/// * `gdata` - The static data of the program, for the commands, their aliases and their number of values
+/// * `command` - The command to describe, with or without its leading `-` (e.g. `add`), or one of its aliases
+/// # Note
+/// Without a command, one line is printed per command, e.g. `-add (or -a), 2 values: Add a directory to the list of supported directories.`
+/// The usage and the description come from `HELP`.
+fn help(gdata: &GlobalData, command: Option<&str>) -> ! {
+    let mut names = gdata
+        .argcs
+        .keys()
+        .chain(gdata.no_dirs.iter())
+        .chain(iter::once(&"-get"))
+        .filter(|name| !gdata.aliass.contains_key(*name))
+        .copied()
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    let describe = |name: &str| {
+        let mut names_of = gdata
+            .aliass
+            .iter()
+            .filter(|(_, full)| **full == name)
+            .map(|(alias, _)| *alias)
+            .collect::<Vec<_>>();
+        names_of.sort_unstable();
+        let aliases = if names_of.is_empty() {
+            String::new()
+        } else {
+            format!(" (or {})", names_of.join(" or "))
+        };
+        let values = match (gdata.argcs.get(name), gdata.nargcs.get(name)) {
+            (Some(1), _) => String::from(", 1 value"),
+            (Some(count), _) => format!(", {count} values"),
+            (None, Some(1)) => String::from(", up to 1 value"),
+            (None, Some(count)) => format!(", up to {count} values"),
+            (None, None) => String::new(),
+        };
+        let (usage, description) = HELP
+            .iter()
+            .find(|(help_name, ..)| *help_name == name)
+            .map_or(("", "No description."), |(_, usage, description)| {
+                (*usage, *description)
+            });
+        (format!("{name}{aliases}{values}: {description}"), usage)
+    };
+    let text = command.map_or_else(
+        || {
+            names.iter().fold(
+                String::from("Usage: . gt [shortcut] [subdirectory], or . gt [command] [values]...\nRun `goto -help [command]` for the usage of a command.\n\n"),
+                |mut text, name| {
+                    writeln!(text, "{}", describe(name).0).write_error("help");
+                    text
+                },
+            )
+        },
+        |given| {
+            let dashed = if given.starts_with('-') {
+                given.to_owned()
+            } else {
+                format!("-{given}")
+            };
+            let name = gdata.aliass.get(dashed.as_str()).copied().unwrap_or(&dashed);
+            if names.contains(&name) {
+                let (line, usage) = describe(name);
+                format!("{line}\nUsage: {usage}\n")
+            } else {
+                user_error!("Unknown command <{given}> for <-help>");
+                String::new()
+            }
+        },
+    );
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{text}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
+
+/// Print the version of `goto`.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// * `verbose` - `true` to also print the OS and the path of the list of supported directories (with `-v`)
+/// # Note
+/// The text will be printed in the following format (the last two lines only if `verbose`):
+/// ```text
+/// goto 0.1.0
+/// OS: unix
+/// Directories: /home/user/.config/goto/dirs.csv
+/// ```
+/// No file is read, so that it also works when the configuration is broken.
+fn version(gdata: &GlobalData, verbose: bool) -> ! {
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        println!("goto {}", env!("CARGO_PKG_VERSION"));
+        if verbose {
+            println!("OS: {}", if gdata.unix { "unix" } else { "windows" });
+            println!("Directories: {}", gdata.dirs);
         }
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-        if !path::Path::new(&hist).exists() {
-            fs::write(&hist, "").write_error(&hist);
+/// Print the files used by the program, and the settings that change how they are modified.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// # Note
+/// The text will be printed in the following format, with one `Shared` line per shared list (see `GOTO_DIRS_PATH`):
+/// ```text
+/// Directories: /home/user/.config/goto/dirs.csv (exists, writable)
+/// History: /home/user/.config/goto/hist.csv (exists, writable)
+/// Shared: /srv/team/dirs.csv (exists, read-only)
+/// Increment: 10
+/// OS: unix
+/// ```
+/// The files are opened to append, without being modified, to check that they are writable.
+fn config(gdata: &GlobalData) -> ! {
+    let state = |path: &str| {
+        if fs::metadata(path).is_err() {
+            "missing"
+        } else if fs::OpenOptions::new().append(true).open(path).is_ok() {
+            "exists, writable"
+        } else {
+            "exists, read-only"
         }
+    };
+    let mut text = String::new();
+    writeln!(text, "Directories: {} ({})", gdata.dirs, state(&gdata.dirs)).write_error("config");
+    writeln!(text, "History: {} ({})", gdata.hist, state(&gdata.hist)).write_error("config");
+    for shared in &gdata.shared {
+        writeln!(text, "Shared: {shared} ({})", state(shared)).write_error("config");
+    }
+    writeln!(text, "Increment: {}", gdata.incr).write_error("config");
+    writeln!(text, "OS: {}", if gdata.unix { "unix" } else { "windows" }).write_error("config");
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{text}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-        Self {
-            dirs,
-            hist,
-            incr: 10,
-            unix,
-            argcs,
-            aliass,
-            no_dirs: &["-noclear", "-code", "-still", "-pop", "-state", "-clear"],
+/// Print the configuration of the program as environment variables.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// # Note
+/// The text will be printed in the following format, and can be sourced by a shell:
+/// ```text
+/// GOTO_DIR='/home/user/.config/goto/'
+/// GOTO_DIRS='/home/user/.config/goto/dirs.csv'
+/// GOTO_DIRS_PATH='/home/user/.config/goto/dirs.csv:/shared/dirs.csv'
+/// GOTO_HIST='/home/user/.config/goto/hist.csv'
+/// GOTO_INCR='10'
+/// GOTO_MAX_PRIORITY='1000000'
+/// GOTO_HIST_TTL='2592000'
+/// GOTO_HIST_MAX_LINES='1000'
+/// GOTO_PER_SHELL_HISTORY='false'
+/// GOTO_FUZZY_WEIGHT='0.5'
+/// GOTO_FUZZY_THRESHOLD='2'
+/// GOTO_MATCH='exact,fuzzy'
+/// GOTO_CASE_SENSITIVE='false'
+/// GOTO_EDITOR='code'
+/// GOTO_CANONICALIZE='true'
+/// GOTO_INTERACTIVE='false'
+/// GOTO_NO_CLEAR='false'
+/// ```
+/// All the settings of `GlobalData` are printed (see `GlobalData::settings`), except `GOTO_OUTPUT_SEP` when it is the default unit separator: it can't be typed between quotes, and the wrappers already use it when the variable isn't set.
+fn export_env(gdata: &GlobalData) -> ! {
+    let settings = gdata.settings();
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    for (name, value) in settings {
+        if name == "OUTPUT_SEP" && value == "\x1f" {
+            continue;
         }
+        println!("GOTO_{name}='{}'", value.replace('\'', "'\\''"));
     }
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
 }
 
-///////////////////////////////: No dirs functions  :///////////////////////////////
+/// Print the wrapper of a shell that can't source the posix script `gt`, or a function that replaces it.
+/// # Arguments
+/// * `shell` - The shell of the wrapper: `cmd`, `nu`, `bash`, `zsh` or `fish`
+/// # Note
+/// The wrapper runs `goto.exe`, and reads its output (`still␟get␟path`, see `PROTOCOL_FORMAT`): it prints the path with `-get`, changes directory unless `-still` is given, and prints the other lines as is.
+/// The wrapper splits the output on `GOTO_OUTPUT_SEP` if it is set, like `goto.exe` (for `cmd`, it must then be a single character).
+/// For `cmd`, save it in a batch file, and define a macro to call it, e.g. in the `AutoRun` script of `cmd`:
+/// ```bat
+/// goto.exe -init cmd > %USERPROFILE%\gt.bat
+/// doskey gt=%USERPROFILE%\gt.bat $*
+/// ```
+/// For `nu`, save it in a file sourced by the configuration of nushell, e.g.:
+/// ```nu
+/// goto -init nu | save -f ~/.config/nushell/gt.nu
+/// source ~/.config/nushell/gt.nu # in config.nu
+/// ```
+/// For `bash`, `zsh` and `fish`, the wrapper is a `gt` function, that works like the `gt` script without being sourced. Evaluate it in `~/.bashrc` or `~/.zshrc`:
+/// ```bash
+/// eval "$(goto -init bash)"
+/// ```
+/// The function returns the exit status of `goto` (e.g. of the editor with `-code`), or of `cd` if it fails.
+/// For `fish`, source it in `~/.config/fish/config.fish`:
+/// ```fish
+/// goto -init fish | source
+/// ```
+/// The `zsh` function splits the output with `${(s:␟:)output}`, and avoids the `path` and `status` variables, which are special in `zsh`.
+fn init(shell: Option<&str>) -> ! {
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{}", init_script(shell));
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
+}
 
-/// Find the path of the directory to go to.
+/// Function to generate the wrapper of a shell, for `-init`.
 /// # Arguments
-/// * `dirs` - The path to the file containing the directories
-/// * `hist` - The path to the file containing the history for popd/pushd
-/// * `args` - The arguments of the command
-/// * `incr` - The increment to add to the usage of the directory
+/// * `shell` - The shell of the wrapper: `cmd`, `nu`, `bash`, `zsh` or `fish`
 /// # Returns
-/// The path of the directory to go to, if the command is valid.
-/// # Warning
-/// If the command is invalid.
-/// # Note
-/// This function is used to find the path of the directory to go to, and to update the usage of the directory if the command is valid.
-/// The function also prints the path of the directory to go to, and calls the `code` function to open the directory in Visual Studio Code.
-/// The function also calls the `clear` function to clear the terminal, unless the `noclear` argument is present.
-fn no_dirs(dirs: &str, hist: &str, args2: &[String]) -> Option<String> {
-    let mut res = None;
+/// The wrapper, checking the protocol of this version of `goto` (empty if the shell isn't supported, after printing an error).
+fn init_script(shell: Option<&str>) -> String {
+    let script = match shell {
+        Some("cmd") => r#"@echo off
+rem The script must be generated again if goto.exe changes the format of its output (see goto.exe -verify-shell)
+for /f "delims=" %%v in ('goto.exe -verify-shell') do if not "%%v"=="PROTOCOL" (
+    echo gt: this script was generated for the protocol PROTOCOL of goto.exe, which now uses %%v: run goto.exe -init cmd again 1>&2
+    exit /b 1
+)
+if defined GOTO_OUTPUT_SEP (set "goto_sep=%GOTO_OUTPUT_SEP%") else set "goto_sep=UNIT_SEPARATOR"
+for /f "delims=" %%l in ('goto.exe %*') do call :goto_line "%%l"
+set "goto_line="
+set "goto_path="
+set "goto_sep="
+exit /b
+
+:goto_line
+set "goto_line=%~1"
+if not "%goto_line:~1,1%%goto_line:~3,1%"=="%goto_sep%%goto_sep%" (
+    echo(%goto_line%
+    exit /b
+)
+set "goto_path=%goto_line:~4%"
+if "%goto_line:~2,1%"=="1" (
+    echo(%goto_path%
+    exit /b
+)
+if not "%goto_line:~0,1%"=="1" if not "%goto_path%"=="" cd /d "%goto_path%"
+exit /b
+"#
+        .replace("UNIT_SEPARATOR", "\x1f"),
+        Some("nu") => "# The function must be generated again if `goto` changes the format of its output (see `goto -verify-shell`)
+let goto_protocol = (do { ^goto -verify-shell } | complete | get stdout | str trim)
+if $goto_protocol != 'PROTOCOL' {
+    print --stderr $\"gt: this function was generated for the protocol PROTOCOL of goto, which now uses ($goto_protocol): run 'goto -init nu' again\"
+}
+def --env --wrapped gt [...args: string] {
+    let id = ($env.GOTO_SHELL_ID? | default ($nu.pid | into string))
+    let output = (with-env {GOTO_SHELL_ID: $id} { ^goto ...$args | lines })
+    if ($output | is-empty) {
+        return
+    }
+    let sep = ($env.GOTO_OUTPUT_SEP? | default (char --unicode '1f'))
+    let fields = ($output | last | split row $sep)
+    if ($fields | length) < 3 or not ($fields.0 in ['0' '1']) or not ($fields.1 in ['0' '1']) {
+        for line in $output {
+            print $line
+        }
+        return
+    }
+    let path = ($fields | skip 2 | str join $sep)
+    if $fields.1 == '1' {
+        print $path
+    } else if $fields.0 != '1' and $path != '' {
+        cd $path
+    }
+}
+"
+        .to_owned(),
+        Some("bash") => INIT_BASH.to_owned(),
+        Some("zsh") => INIT_ZSH.to_owned(),
+        Some("fish") => INIT_FISH.to_owned(),
+        Some(value) => {
+            user_error!("Invalid shell <{value}> for <-init>: expected cmd, nu, bash, zsh or fish");
+            String::new()
+        }
+        None => {
+            user_error!("Missing shell for <-init>: expected cmd, nu, bash, zsh or fish");
+            String::new()
+        }
+    };
+    script.replace("PROTOCOL", &protocol())
+}
 
-    args2.iter().for_each(|arg| match arg.as_str() {
-        "-pop" => res = Some(hist::popd(hist)),
-        "-state" => dirs::state(dirs),
-        "-clear" => fs::write(dirs, "").write_error(dirs),
-        "-code" | "-noclear" | "-still" => (),
-        _ => user_error!(
-            "Invalid command <{}> in <{}>",
-            arg,
-            env::args().collect::<Vec<String>>().join(" ")
+/// Print the completion script of a shell.
+/// # Arguments
+/// * `gdata` - The static data of the program
+/// * `shell` - The shell to complete in: `bash`, `zsh` or `fish`
+/// # Note
+/// The options are written in the script, but the shortcuts are listed at runtime with `goto -shortcuts`, so that they stay in sync with the list of supported directories.
+/// The commands `g`, `gt` and `goto` are completed (`. gt` can't be completed, as the command is `.`: use an alias like `alias g='. gt'`).
+fn completions(gdata: &GlobalData, shell: Option<&str>) -> ! {
+    let mut options = gdata
+        .argcs
+        .keys()
+        .chain(gdata.aliass.keys())
+        .chain(gdata.no_dirs.iter())
+        .chain(iter::once(&"-get"))
+        .filter(|opt| opt.starts_with('-'))
+        .copied()
+        .collect::<Vec<_>>();
+    options.sort_unstable();
+    options.dedup();
+    let opts = options.join(" ");
+    let script = match shell {
+        Some("--shortcuts") => dirs::shortcuts(&gdata.dirs),
+        Some("bash") => format!(
+            r#"_goto_complete() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ $cur == -* ]]; then
+        COMPREPLY=($(compgen -W "{opts}" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -W "$(goto -shortcuts 2>/dev/null)" -- "$cur"))
+    fi
+}}
+complete -o default -F _goto_complete g gt goto
+"#
         ),
-    });
-    res
+        Some("zsh") => format!(
+            r#"_goto_complete() {{
+    local -a candidates
+    candidates=(${{(f)"$(goto -shortcuts 2>/dev/null)"}} {opts})
+    compadd -a candidates
+    _files -/
+}}
+compdef _goto_complete g gt goto
+"#
+        ),
+        Some("fish") => ["g", "gt", "goto"]
+            .iter()
+            .fold(String::new(), |mut acc, cmd| {
+                writeln!(
+                    acc,
+                    "complete -c {cmd} -f -a '(goto -shortcuts 2>/dev/null)'\ncomplete -c {cmd} -f -a '{opts}'"
+                )
+                .write_error("lines");
+                acc
+            }),
+        Some(value) => {
+            user_error!("Invalid shell <{value}> for <-completions>: expected bash, zsh or fish");
+            String::new()
+        }
+        None => {
+            user_error!("Missing shell for <-completions>: expected bash, zsh or fish");
+            String::new()
+        }
+    };
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
+    {
+        print!("{script}");
+    };
+    #[expect(
+        clippy::exit,
+        reason = "the process ends with the status of the command"
+    )]
+    process::exit(0);
 }
 
-/// Open the directory in Visual Studio Code.
+/// Open the directory in the editor.
 /// # Arguments
 /// * `args2` - The arguments of the command
+/// * `editor` - The command of the editor (see `GlobalData`)
 /// * `path` - The path of the directory to open
 /// # Note
-/// This function is used to open the directory in Visual Studio Code, if the `code` argument is present.
-/// The function uses the `code` command to open the directory in Visual Studio Code.
-/// The function raises a warning if the `code` command is not found.
+/// This function is used to open the directory in the editor, if the `code` argument is present.
+/// The function uses the `GOTO_EDITOR` command (`code` by default) to open the directory.
+/// The function raises a warning if the command is not found.
 /// The function is called after finding the path of the directory to go to, and after updating the usage of the directory.
 ///
-fn vscode(args2: &[String], path: &str) {
-    if args2.contains(&String::from("-code")) {
-        match process::Command::new("code").arg(path).spawn() {
+fn open_editor(args2: &[Opt], editor: &str, path: &str) {
+    if args2.has("-code") {
+        match process::Command::new(editor).arg(path).spawn() {
             Ok(mut subprocesses) => {
-                subprocesses.wait().command_error("Unable to open VSCode");
+                subprocesses
+                    .wait()
+                    .command_error(&format!("Unable to open {editor}"));
             }
-            Err(er) => command_error!("Unable to open VSCode: {er}"),
-        };
+            Err(er) => command_error!(
+                "Unable to open {editor}: {er}.\nSet GOTO_EDITOR to the command of your editor (e.g. `export GOTO_EDITOR=nvim`)"
+            ),
+        }
     }
 }
 
@@ -261,140 +1079,176 @@ fn vscode(args2: &[String], path: &str) {
 /// # Arguments
 /// * `args2` - The `no_dirs` arguments of the command
 /// * `get` - `true` if the `get` argument is present, `false` otherwise
+/// * `gdata` - The global data, for the commands that print data to stdout and `GOTO_NO_CLEAR`
 /// # Note
-/// This function is used to clear the terminal, unless the `-noclear` argument is present.
-/// The function is called after opening the directory in Visual Studio Code, and after updating the usage of the directory.
-/// The function is also called at the beginning of the program, to clear the terminal before the command is executed.
-#[allow(clippy::print_stderr)]
-fn clear_terminal(args2: &[String], get: bool) {
-    if !args2.contains(&String::from("-noclear")) && !get {
+/// This function is used to clear the terminal, unless the `-noclear` argument is present or `GOTO_NO_CLEAR` is set.
+/// The terminal is not cleared either if a command prints data to stdout (`-get`, `-state`, ...), so that the output can be piped or read.
+/// The terminal is cleared through stderr, as stdout is read by the shell wrapper, and only if stderr is a terminal (not when it is redirected to a file).
+/// On windows, `cls` is used, as `cmd` doesn't support the ANSI codes.
+/// The function is called at the beginning of the program, to clear the terminal before the command is executed.
+#[expect(clippy::print_stderr, reason = "the errors are printed for the user")]
+fn clear_terminal(args2: &[Opt], get: bool, gdata: &GlobalData) {
+    let output = args2
+        .iter()
+        .any(|opt| gdata.outputs.contains(&opt.name.as_str()));
+    if args2.has("-noclear") || get || output || gdata.no_clear || !io::stderr().is_terminal() {
+        return;
+    }
+    if cfg!(windows) {
+        process::Command::new("cmd")
+            .args(["/C", "cls"])
+            .stdout(io::stderr())
+            .status()
+            .map(drop)
+            .system_error("Unable to clear the terminal");
+    } else {
         eprint!("\x1B[2J\x1B[1;1H");
     }
 }
 
 ///////////////////////////////: goto functions  :///////////////////////////////
 
-/// Get the arguments of the command.
-/// # Arguments
-/// * `argcs` - The number of arguments for each supported command
-/// # Returns
-/// A tuple containing the arguments of the command, and the arguments of the command that are not part of the command.
-/// # Note
-/// This function is used to get the arguments of the command, and to separate the arguments of the command that are not part of the command.
-/// The function also checks that the number of arguments of the command is valid.
-/// The function is called at the beginning of the program, to get the arguments of the command, and to separate the arguments of the command that are not part of the command.
-/// The function is also used to check that the number of arguments of the command is valid.
-/// The function is also used to separate the arguments of the command that are not part of the command.
-///
-//
-fn get_args(gdata: &GlobalData) -> (Vec<Cmd>, Vec<String>, bool) {
-    let mut cmdline = env::args().skip(1);
-    let mut args1: Vec<Cmd> = vec![];
-    let mut args2: Vec<String> = vec![];
-    let mut get = false;
-    let here11 = env::current_dir();
-    let here22 = here11.command_error("Unable to get current directory. Access denied.");
-    let here = here22
-        .to_str()
-        .command_error("Unable to convert current directory to string. Access denied.");
-
-    loop {
-        let temp = cmdline.next();
-        match temp {
-            None => break,
-            Some(arg) => {
-                let curr = (*gdata.aliass.get(arg.as_str()).unwrap_or(&arg.as_str())).to_owned();
-
-                match gdata.argcs.get(curr.as_str()) {
-                    Some(value) => {
-                        if cmdline.len() < *value {
-                            args1.last_mut().append_default(here);
-                        };
-                        args1.push(curr.to_cmd());
-                    }
+///////////////////////////////: Main  :///////////////////////////////
 
-                    None => match curr.as_str() {
-                        "features" => break,
-                        "-get" => {
-                            get = true;
-                            args1.push(curr.to_cmd());
-                        }
-                        // Is a no_dirs command (code, clear, still, pop, state, noclear, etc.)
-                        _ if gdata.no_dirs.contains(&curr.as_str()) => args2.push(curr.clone()),
-                        // Is an argument to a previous option
-                        _ => match args1.last_mut() {
-                            None => args1.push(Cmd::Get(ShortPath {
-                                short: Some(curr.clone()),
-                                path: None,
-                            })),
-                            Some(last) => last.append(curr),
-                        },
-                    },
-                }
-            }
-        };
+fn main() {
+    let mut gdata = GlobalData::default();
+    // Nothing is done with the paths built from an inaccessible current directory
+    let (mut args1, args2, get) =
+        goto::parse_args(&gdata, &env::args().skip(1).collect::<Vec<_>>()).unwrap_or_else(|er| {
+            user_error!("{er}");
+            process::exit(1);
+        });
+    // `--weight` overrides `GOTO_INCR` for this call (e.g. `0` in a script, so that it doesn't change the priorities)
+    if let Some(opt) = args2.iter().find(|opt| opt.name == "--weight") {
+        match opt.values.first() {
+            Some(weight) => gdata.incr = goto::parse_incr(weight, "--weight"),
+            None => user_error!("Missing value for <--weight>"),
+        }
     }
-
-    args1.last_mut().append_default(here);
-    if args1.is_empty() {
-        args1.push(Cmd::default());
+    // The commands that only print don't touch the disk, e.g. to work with a broken configuration
+    if !["-version", "-help", "-init", "-completions"]
+        .iter()
+        .any(|name| args2.has(name))
+    {
+        gdata.create_files();
     }
-
-    (args1, args2, get)
-}
-
-/// Convers path to unix or dos, depending on the OS.
-/// # Arguments
-/// * `ipath` - A path in DOS or UNIX format
-/// * `unix` - `true` if the path should be converted to UNIX format, `false` if the path should be converted to DOS format
-/// # Returns
-/// The path in DOS or UNIX format, depending on the OS.
-fn dos2unix(path: String, unix: bool) -> String {
-    let chars: Vec<char> = path.chars().collect();
-    if chars.get(1) == Some(&':') && unix {
-        let rest: String = chars.get(3..).unwrap_or_default().iter().collect();
-        format!("/mnt/{}/{}", chars.first().unwrap_or(&' '), rest)
-    } else if path.contains("wsl.localhost") && unix {
-        let index = path.find("wsl.localhost").unwrap_or(0);
-        path.get(index..).unwrap_or_default().to_owned()
-    } else if path.starts_with("/mnt/") && !unix {
-        let rest: String = chars.get(6..).unwrap_or_default().iter().collect();
-        format!("{}:{}", chars.get(5).unwrap_or(&'c'), rest)
-    } else {
-        path
+    clear_terminal(&args2, get, &gdata);
+    for cmd in &mut args1 {
+        if let Cmd::Rm(shorts) = cmd {
+            if shorts.is_empty() || *shorts == ["-i"] {
+                *shorts = dirs::select_shorts(&gdata.dirs);
+            }
+        }
+        if gdata.canonicalize {
+            cmd.canonicalize();
+        }
     }
-}
+    // `no_dirs` first, so that the commands that exit (e.g. `-sweep --dry-run`) see the file untouched
+    let pop_path = no_dirs(&gdata, &args2); // result of pop
 
-///////////////////////////////: Main  :///////////////////////////////
-
-fn main() {
-    let gdata = GlobalData::default();
-    let (args1, args2, get) = get_args(&gdata);
-    clear_terminal(&args2, get);
-    let short_path = dirs::read(&gdata.dirs, &args1, gdata.incr);
+    // `-state` only prints the list, unless a directory is also asked for (e.g. `-state -code foo`)
+    if args2.has("-state")
+        && !args2.has("-code")
+        && matches!(
+            args1.as_slice(),
+            [Cmd::Get(ShortPath {
+                short: None,
+                path: None
+            })]
+        )
+    {
+        process::exit(0);
+    }
 
-    let pop_path = no_dirs(&gdata.dirs, &gdata.hist, &args2); // result of pop
+    // `-add --to` and `--global` modify another file of shortcuts (e.g. a shared one, see `GOTO_DIRS_PATH`)
+    let short_path = dirs::target_file(&args1, &gdata).and_then(|dpath| {
+        dirs::read(
+            dpath,
+            &args1,
+            &gdata,
+            args2.has("-force"),
+            args2.has("-explain"),
+        )
+    });
 
     let read = pop_path.as_ref().is_none() && short_path.as_ref().is_some();
 
-    let os_path = dos2unix(
+    let os_path = goto::dos2unix(
         pop_path.unwrap_or_else(|| short_path.unwrap_or_default()),
         gdata.unix,
     );
 
     if read {
-        hist::pushd(&gdata.hist, &os_path);
-    };
+        hist::pushd(&gdata, &os_path);
+    }
+
+    open_editor(&args2, &gdata.editor, &os_path);
 
-    vscode(&args2, &os_path);
+    // With `-raw`, only the path is printed, for the scripts
+    if args2.has("-raw") {
+        if os_path.is_empty() {
+            process::exit(1);
+        }
+        #[expect(
+            clippy::print_stdout,
+            reason = "the output is read by the user or the shell wrapper"
+        )]
+        {
+            println!("{os_path}");
+        };
+        return;
+    }
 
-    #[allow(clippy::print_stdout)]
+    #[expect(
+        clippy::print_stdout,
+        reason = "the output is read by the user or the shell wrapper"
+    )]
     {
         print!(
-            "{}#{}#{}",
-            u8::from(args2.contains(&String::from("-still"))),
+            "{}{sep}{}{sep}{}",
+            u8::from(args2.has("-still") || args2.has("-explain")),
             u8::from(get),
-            &os_path
+            os_path,
+            sep = gdata.output_sep
         );
     };
 }
+
+#[cfg(test)]
+#[expect(
+    clippy::inline_modules,
+    reason = "the unit tests are next to the code they test"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrappers_check_the_protocol() {
+        for wrapper in [
+            include_str!("../release/g"),
+            include_str!("../release/g.ps1"),
+            include_str!("../release/gtexe.ps1"),
+        ] {
+            assert!(wrapper.contains("-verify-shell"));
+            assert!(wrapper.contains(&protocol()));
+        }
+        for shell in ["cmd", "nu", "bash", "zsh", "fish"] {
+            let wrapper = init_script(Some(shell));
+            assert!(wrapper.contains("-verify-shell"));
+            assert!(wrapper.contains(&protocol()));
+            assert!(!wrapper.contains("PROTOCOL"));
+        }
+    }
+
+    #[test]
+    fn wrappers_pass_the_arguments_as_is() {
+        for wrapper in [
+            include_str!("../release/c"),
+            include_str!("../release/e"),
+            include_str!("../release/g"),
+        ] {
+            assert!(wrapper.contains("\"$@\""));
+            assert!(!wrapper.contains("eval") && !wrapper.contains(".ps1"));
+        }
+    }
+}